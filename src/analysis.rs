@@ -0,0 +1,284 @@
+use crate::json::Json;
+use crate::mission_instructions::{Compass, Coordinates, RobotCommands};
+use crate::plateau::Plateau;
+use crate::report::coordinates_json;
+use crate::robots::{RobotPosition, RobotStatus};
+use crate::scents::{KnownScents, ScentStore, HEADINGS};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// What a forward move from a state would do to the robot.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Danger {
+    Safe,
+    /// Would lose the robot, but a known scent means the move is ignored.
+    Protected,
+    /// Loses or crashes the robot.
+    Fatal,
+}
+
+impl fmt::Display for Danger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Danger::Safe => write!(f, "safe"),
+            Danger::Protected => write!(f, "protected"),
+            Danger::Fatal => write!(f, "fatal"),
+        }
+    }
+}
+
+/// How dangerous a forward move is from every square and heading a robot
+/// could be in on a plateau, given the scents already known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DangerMap {
+    pub plateau: Plateau,
+    /// Every open square with every heading the compass allows, bottom row
+    /// first and clockwise from north.
+    pub states: Vec<(RobotPosition, Danger)>,
+    /// The fewest robots that would have to be lost before every move off the
+    /// plateau is covered by a scent. Crashes into obstacles can't be scented
+    /// and don't count.
+    pub minimum_losses: usize,
+}
+
+impl DangerMap {
+    pub fn at<'a>(
+        &'a self,
+        square: &'a Coordinates,
+    ) -> impl Iterator<Item = &'a (RobotPosition, Danger)> + 'a {
+        self.states
+            .iter()
+            .filter(move |(position, _)| position.coordinates == *square)
+    }
+
+    pub fn count(&self, danger: Danger) -> usize {
+        self.states
+            .iter()
+            .filter(|(_, state)| *state == danger)
+            .count()
+    }
+
+    pub fn text(&self) -> String {
+        let mut output = String::new();
+        for (position, danger) in &self.states {
+            if *danger != Danger::Safe {
+                output.push_str(&format!("{} {}\n", position, danger));
+            }
+        }
+        output.push_str(&format!(
+            "fatal {}, protected {}, safe {}\n",
+            self.count(Danger::Fatal),
+            self.count(Danger::Protected),
+            self.count(Danger::Safe)
+        ));
+        output.push_str(&format!(
+            "minimum losses to scent the perimeter: {}\n",
+            self.minimum_losses
+        ));
+        output
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            (
+                "grid",
+                Json::object(vec![
+                    (
+                        "lower_left",
+                        coordinates_json(&self.plateau.bounds.lower_left),
+                    ),
+                    (
+                        "upper_right",
+                        coordinates_json(&self.plateau.bounds.upper_right),
+                    ),
+                ]),
+            ),
+            ("scent_policy", Json::string(self.plateau.scent_policy)),
+            (
+                "states",
+                Json::Array(
+                    self.states
+                        .iter()
+                        .map(|(position, danger)| {
+                            Json::object(vec![
+                                ("x", Json::from(position.coordinates.x)),
+                                ("y", Json::from(position.coordinates.y)),
+                                ("orientation", Json::string(&position.orientation)),
+                                ("danger", Json::string(danger)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("minimum_losses", Json::from(self.minimum_losses)),
+        ])
+    }
+}
+
+fn forward_status<S: ScentStore>(
+    position: &RobotPosition,
+    plateau: &Plateau,
+    scents: &S,
+) -> RobotStatus {
+    RobotCommands::Forward
+        .process(position.clone(), plateau, &mut KnownScents(scents))
+        .1
+}
+
+/// The fewest of `exits`, all on one square, that need to be lost from so
+/// every one of them ends up scented. Scents only ever protect moves from
+/// their own square, so squares can be worked out one at a time.
+fn losses_to_scent(
+    exits: &[RobotPosition],
+    plateau: &Plateau,
+    known: &HashSet<RobotPosition>,
+) -> usize {
+    (0u32..1 << exits.len())
+        .filter(|losses| {
+            let mut scents = known.clone();
+            for (index, exit) in exits.iter().enumerate() {
+                if losses & (1 << index) != 0 {
+                    plateau.scent_policy.record(exit, plateau, &mut scents);
+                }
+            }
+            exits
+                .iter()
+                .all(|exit| forward_status(exit, plateau, &scents) == RobotStatus::Alive)
+        })
+        .map(|losses| losses.count_ones() as usize)
+        .min()
+        .unwrap_or(exits.len())
+}
+
+/// Classifies a forward move from every state on the plateau, and works out
+/// how many losses it would take to scent every way off it that isn't
+/// already.
+pub fn analyze<S: ScentStore>(plateau: &Plateau, compass: &Compass, scents: &S) -> DangerMap {
+    let bounds = &plateau.bounds;
+    let no_scents: HashSet<RobotPosition> = HashSet::new();
+    let mut states = vec![];
+    let mut exits: BTreeMap<(i32, i32), Vec<RobotPosition>> = BTreeMap::new();
+
+    for y in bounds.lower_left.y..=bounds.upper_right.y {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            if plateau.off_plateau(&square) || plateau.is_blocked(&square) {
+                continue;
+            }
+            for heading in HEADINGS
+                .iter()
+                .filter(|heading| compass.supports_orientation(heading))
+            {
+                let position = RobotPosition {
+                    coordinates: square.clone(),
+                    orientation: heading.clone(),
+                };
+                let unscented = forward_status(&position, plateau, &no_scents);
+                let danger = if unscented == RobotStatus::Alive {
+                    Danger::Safe
+                } else if unscented == RobotStatus::Crashed {
+                    // scents only stop robots leaving the plateau
+                    Danger::Fatal
+                } else if forward_status(&position, plateau, scents) == RobotStatus::Alive {
+                    Danger::Protected
+                } else {
+                    Danger::Fatal
+                };
+                if danger == Danger::Fatal && unscented == RobotStatus::Lost {
+                    exits.entry((x, y)).or_default().push(position.clone());
+                }
+                states.push((position, danger));
+            }
+        }
+    }
+
+    let known: HashSet<RobotPosition> = scents.scents().into_iter().collect();
+    let minimum_losses = exits
+        .values()
+        .map(|exits| losses_to_scent(exits, plateau, &known))
+        .sum();
+
+    DangerMap {
+        plateau: plateau.clone(),
+        states,
+        minimum_losses,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analysis::{analyze, Danger};
+    use crate::mission_instructions::Coordinates;
+    use crate::parser::parse_input_to_command;
+    use crate::robots::RobotPosition;
+    use rstest::rstest;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn danger_of(mission: &str, scents: &[&str], state: &str) -> Danger {
+        let command = parse_input_to_command(mission).unwrap();
+        let scents: HashSet<RobotPosition> = scents
+            .iter()
+            .map(|scent| RobotPosition::from_str(scent).unwrap())
+            .collect();
+        let map = analyze(&command.plateau(), &command.compass, &scents);
+        let state = RobotPosition::from_str(state).unwrap();
+        map.states
+            .into_iter()
+            .find(|(position, _)| *position == state)
+            .map(|(_, danger)| danger)
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case("5 3\n", &[], "2 2 N", Danger::Safe)]
+    #[case("5 3\n", &[], "3 3 N", Danger::Fatal)]
+    #[case("5 3\n", &["3 3 N"], "3 3 N", Danger::Protected)]
+    #[case("5 3\n", &["3 3 N"], "3 3 E", Danger::Safe)]
+    #[case("scent square\n5 3\n", &["5 3 N"], "5 3 E", Danger::Protected)]
+    #[case("boundary wrap\n5 3\n", &[], "0 0 W", Danger::Safe)]
+    #[case("obstacle 2 2\n5 3\n", &[], "2 1 N", Danger::Safe)]
+    #[case("obstacle 2 2\non-obstacle crash\n5 3\n", &[], "2 1 N", Danger::Fatal)]
+    #[case("obstacle 2 2\non-obstacle crash\n5 3\n", &["2 1 N"], "2 1 N", Danger::Fatal)]
+    fn test_danger(
+        #[case] mission: &str,
+        #[case] scents: &[&str],
+        #[case] state: &str,
+        #[case] expected: Danger,
+    ) {
+        assert_eq!(danger_of(mission, scents, state), expected);
+    }
+
+    #[rstest]
+    // every edge square has one way off, the corners two
+    #[case("5 3\n", 20)]
+    #[case("scent square\n5 3\n", 16)]
+    // a diagonal loss at a corner scents both of its sides
+    #[case("compass 8\nscent edge\n5 3\n", 16)]
+    #[case("compass 8\n5 3\n", 56)]
+    #[case("boundary clamp\n5 3\n", 0)]
+    #[case("obstacle 2 2\non-obstacle crash\n5 3\n", 20)]
+    fn test_minimum_losses(#[case] mission: &str, #[case] expected: usize) {
+        let command = parse_input_to_command(mission).unwrap();
+        let scents: HashSet<RobotPosition> = HashSet::new();
+
+        let map = analyze(&command.plateau(), &command.compass, &scents);
+
+        assert_eq!(map.minimum_losses, expected);
+    }
+
+    #[test]
+    fn test_known_scents_are_not_counted() {
+        let command = parse_input_to_command("1 0\n").unwrap();
+        let scents: HashSet<RobotPosition> = ["0 0 N", "0 0 W", "0 0 S", "1 0 N", "1 0 S"]
+            .iter()
+            .map(|scent| RobotPosition::from_str(scent).unwrap())
+            .collect();
+
+        let map = analyze(&command.plateau(), &command.compass, &scents);
+
+        assert_eq!(map.minimum_losses, 1);
+        assert_eq!(map.count(Danger::Fatal), 1);
+        assert_eq!(map.at(&Coordinates { x: 1, y: 0 }).count(), 4);
+    }
+}
@@ -0,0 +1,838 @@
+use crate::lint::Severity;
+use crate::mission_instructions::RobotCommands;
+use crate::planner::Goal;
+use crate::robots::RobotPosition;
+use crate::scents::ScentsAction;
+use crate::simulation::Schedule;
+use anyhow::anyhow;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// How the end of mission results get printed.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Ascii,
+    Svg,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ascii" => Ok(OutputFormat::Ascii),
+            "svg" => Ok(OutputFormat::Svg),
+            _ => Err(anyhow!(
+                "Output must be one of text, json, ascii or svg, got {}",
+                input
+            )),
+        }
+    }
+}
+
+/// How `heatmap` prints the visits and losses it added up.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum HeatmapFormat {
+    #[default]
+    Ascii,
+    Csv,
+    Svg,
+}
+
+impl FromStr for HeatmapFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "ascii" => Ok(HeatmapFormat::Ascii),
+            "csv" => Ok(HeatmapFormat::Csv),
+            "svg" => Ok(HeatmapFormat::Svg),
+            _ => Err(anyhow!(
+                "Heatmap output must be one of ascii, csv or svg, got {}",
+                input
+            )),
+        }
+    }
+}
+
+/// Which `ScentStore` keeps track of scents during the run.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ScentStoreKind {
+    #[default]
+    Memory,
+    Bitset,
+}
+
+impl FromStr for ScentStoreKind {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "memory" => Ok(ScentStoreKind::Memory),
+            "bitset" => Ok(ScentStoreKind::Bitset),
+            _ => Err(anyhow!(
+                "Scent store must be memory or bitset, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ScentStoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScentStoreKind::Memory => write!(f, "memory"),
+            ScentStoreKind::Bitset => write!(f, "bitset"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Options {
+    pub output: OutputFormat,
+    pub scent_store: ScentStoreKind,
+    /// Scents are loaded from here before the run and saved back after it.
+    pub scent_file: Option<PathBuf>,
+    pub schedule: Schedule,
+    /// Adds aggregate numbers for the mission to text and JSON output.
+    pub stats: bool,
+    /// Every tick of the run is written here for `replay` to check later.
+    pub replay_log: Option<PathBuf>,
+}
+
+/// Where `plan` should find a route from and to. The plateau comes from the
+/// mission on stdin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanOptions {
+    pub from: RobotPosition,
+    pub to: Goal,
+    /// Scents to steer clear of, as left by earlier missions.
+    pub scent_file: Option<PathBuf>,
+}
+
+/// Whether `lint` should fail, for use as a CI gate.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LintOptions {
+    /// Exit with an error when any finding is at least this severe.
+    pub fail_on: Option<Severity>,
+}
+
+/// What `solve` is asked about a command string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveQuery {
+    /// Which starts end alive at the goal.
+    EndAt(Goal),
+    /// Which starts lose the robot, and over which edge.
+    Lost,
+}
+
+/// The commands `solve` runs from every start on the mission's plateau.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveOptions {
+    pub commands: Vec<RobotCommands>,
+    pub query: SolveQuery,
+    /// Scents already known, as left by earlier missions.
+    pub scent_file: Option<PathBuf>,
+}
+
+/// How many robots `cover` may split the survey between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoverOptions {
+    pub robots: usize,
+}
+
+impl Default for CoverOptions {
+    fn default() -> Self {
+        CoverOptions { robots: 1 }
+    }
+}
+
+/// How `analyze` should print the danger map of the mission's plateau.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnalyzeOptions {
+    pub output: OutputFormat,
+    /// Scents already known, as left by earlier missions.
+    pub scent_file: Option<PathBuf>,
+}
+
+/// Which mission file `optimize` should shorten, and whether to write the
+/// shorter commands back into it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizeOptions {
+    pub mission: PathBuf,
+    pub write: bool,
+}
+
+/// The mission files `heatmap` adds up, how to run them and how to print
+/// the result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapOptions {
+    pub output: HeatmapFormat,
+    pub missions: Vec<PathBuf>,
+    /// Scents every mission starts with. The file isn't changed.
+    pub scent_file: Option<PathBuf>,
+    pub schedule: Schedule,
+}
+
+/// The replay log to check the mission on stdin against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOptions {
+    pub log: PathBuf,
+}
+
+/// The mission file `debug` steps through, the rest of stdin being the
+/// `DebugCommand`s to step it with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugOptions {
+    pub mission: PathBuf,
+    pub schedule: Schedule,
+}
+
+/// One line typed at the `debug` prompt.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DebugCommand {
+    Next(usize),
+    Back(usize),
+    Seek(usize),
+    End,
+    Quit,
+}
+
+impl FromStr for DebugCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let count = |word: Option<&&str>| match word {
+            Some(word) => word
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Expected a number of ticks, got {}", word)),
+            None => Ok(1),
+        };
+        match words.first().copied() {
+            None | Some("n") | Some("next") if words.len() <= 2 => {
+                Ok(DebugCommand::Next(count(words.get(1))?))
+            }
+            Some("b") | Some("back") if words.len() <= 2 => {
+                Ok(DebugCommand::Back(count(words.get(1))?))
+            }
+            Some("seek") if words.len() == 2 => Ok(DebugCommand::Seek(count(words.get(1))?)),
+            Some("end") if words.len() == 1 => Ok(DebugCommand::End),
+            Some("q") | Some("quit") if words.len() == 1 => Ok(DebugCommand::Quit),
+            _ => Err(anyhow!(
+                "Commands are next [ticks], back [ticks], seek <tick>, end and quit"
+            )),
+        }
+    }
+}
+
+/// What the program was asked to do. Running a mission read from stdin is the
+/// default, everything else is a subcommand named by the first argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cli {
+    Run(Options),
+    Scents(ScentsAction),
+    Plan(PlanOptions),
+    Optimize(OptimizeOptions),
+    Analyze(AnalyzeOptions),
+    Cover(CoverOptions),
+    Solve(SolveOptions),
+    Lint(LintOptions),
+    Heatmap(HeatmapOptions),
+    Replay(ReplayOptions),
+    Debug(DebugOptions),
+}
+
+pub fn parse_args<I>(args: I) -> Result<Cli, anyhow::Error>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("scents") => {
+            args.next();
+            Ok(Cli::Scents(parse_scents_action(args)?))
+        }
+        Some("plan") => {
+            args.next();
+            Ok(Cli::Plan(parse_plan_options(args)?))
+        }
+        Some("optimize") => {
+            args.next();
+            Ok(Cli::Optimize(parse_optimize_options(args)?))
+        }
+        Some("analyze") => {
+            args.next();
+            Ok(Cli::Analyze(parse_analyze_options(args)?))
+        }
+        Some("cover") => {
+            args.next();
+            Ok(Cli::Cover(parse_cover_options(args)?))
+        }
+        Some("solve") => {
+            args.next();
+            Ok(Cli::Solve(parse_solve_options(args)?))
+        }
+        Some("lint") => {
+            args.next();
+            Ok(Cli::Lint(parse_lint_options(args)?))
+        }
+        Some("heatmap") => {
+            args.next();
+            Ok(Cli::Heatmap(parse_heatmap_options(args)?))
+        }
+        Some("replay") => {
+            args.next();
+            Ok(Cli::Replay(parse_replay_options(args)?))
+        }
+        Some("debug") => {
+            args.next();
+            Ok(Cli::Debug(parse_debug_options(args)?))
+        }
+        _ => Ok(Cli::Run(parse_run_options(args)?)),
+    }
+}
+
+fn parse_scents_action(args: impl Iterator<Item = String>) -> Result<ScentsAction, anyhow::Error> {
+    let args: Vec<String> = args.collect();
+    match args.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+        ["list", path] => Ok(ScentsAction::List(path.into())),
+        ["clear", path] => Ok(ScentsAction::Clear(path.into())),
+        ["merge", target, ref sources @ ..] if !sources.is_empty() => Ok(ScentsAction::Merge {
+            target: target.into(),
+            sources: sources.iter().map(PathBuf::from).collect(),
+        }),
+        _ => Err(anyhow!(
+            "Usage: scents list <file> | scents merge <target> <source>... | scents clear <file>"
+        )),
+    }
+}
+
+fn parse_plan_options(args: impl Iterator<Item = String>) -> Result<PlanOptions, anyhow::Error> {
+    let mut from = None;
+    let mut to = None;
+    let mut scent_file = None;
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = Some(RobotPosition::from_str(&flag_value(&arg, &mut args)?)?),
+            "--to" => to = Some(Goal::from_str(&flag_value(&arg, &mut args)?)?),
+            "--scent-file" => scent_file = Some(flag_value(&arg, &mut args)?.into()),
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+
+    match (from, to) {
+        (Some(from), Some(to)) => Ok(PlanOptions {
+            from,
+            to,
+            scent_file,
+        }),
+        _ => Err(anyhow!(
+            "Usage: plan --from \"x y heading\" --to \"x y [heading]\" [--scent-file <file>]"
+        )),
+    }
+}
+
+fn parse_optimize_options(
+    args: impl Iterator<Item = String>,
+) -> Result<OptimizeOptions, anyhow::Error> {
+    let mut mission = None;
+    let mut write = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--write" => write = true,
+            _ if arg.starts_with("--") => return Err(anyhow!("Unknown argument: {}", arg)),
+            _ if mission.is_none() => mission = Some(arg.into()),
+            _ => return Err(anyhow!("Unexpected argument: {}", arg)),
+        }
+    }
+
+    match mission {
+        Some(mission) => Ok(OptimizeOptions { mission, write }),
+        None => Err(anyhow!("Usage: optimize <mission file> [--write]")),
+    }
+}
+
+fn parse_analyze_options(
+    args: impl Iterator<Item = String>,
+) -> Result<AnalyzeOptions, anyhow::Error> {
+    let mut options = AnalyzeOptions::default();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => options.output = OutputFormat::from_str(&flag_value(&arg, &mut args)?)?,
+            "--scent-file" => options.scent_file = Some(flag_value(&arg, &mut args)?.into()),
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_cover_options(args: impl Iterator<Item = String>) -> Result<CoverOptions, anyhow::Error> {
+    let mut options = CoverOptions::default();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--robots" => options.robots = usize::from_str(&flag_value(&arg, &mut args)?)?,
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_solve_options(args: impl Iterator<Item = String>) -> Result<SolveOptions, anyhow::Error> {
+    let mut commands = None;
+    let mut query = None;
+    let mut scent_file = None;
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--commands" => {
+                commands = Some(
+                    flag_value(&arg, &mut args)?
+                        .chars()
+                        .map(|command| RobotCommands::from_str(&command.to_string()))
+                        .collect::<Result<Vec<RobotCommands>, anyhow::Error>>()?,
+                )
+            }
+            "--to" => {
+                query = Some(SolveQuery::EndAt(Goal::from_str(&flag_value(
+                    &arg, &mut args,
+                )?)?))
+            }
+            "--lost" => query = Some(SolveQuery::Lost),
+            "--scent-file" => scent_file = Some(flag_value(&arg, &mut args)?.into()),
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+
+    match (commands, query) {
+        (Some(commands), Some(query)) => Ok(SolveOptions {
+            commands,
+            query,
+            scent_file,
+        }),
+        _ => Err(anyhow!(
+            "Usage: solve --commands <commands> (--to \"x y [heading]\" | --lost) [--scent-file <file>]"
+        )),
+    }
+}
+
+fn parse_lint_options(args: impl Iterator<Item = String>) -> Result<LintOptions, anyhow::Error> {
+    let mut options = LintOptions::default();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fail-on" => {
+                options.fail_on = Some(Severity::from_str(&flag_value(&arg, &mut args)?)?)
+            }
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_heatmap_options(
+    args: impl Iterator<Item = String>,
+) -> Result<HeatmapOptions, anyhow::Error> {
+    let mut output = HeatmapFormat::default();
+    let mut missions = vec![];
+    let mut scent_file = None;
+    let mut schedule = Schedule::default();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = HeatmapFormat::from_str(&flag_value(&arg, &mut args)?)?,
+            "--scent-file" => scent_file = Some(flag_value(&arg, &mut args)?.into()),
+            "--schedule" => schedule = Schedule::from_str(&flag_value(&arg, &mut args)?)?,
+            _ if arg.starts_with("--") => return Err(anyhow!("Unknown argument: {}", arg)),
+            _ => missions.push(arg.into()),
+        }
+    }
+
+    if missions.is_empty() {
+        return Err(anyhow!(
+            "Usage: heatmap [--output ascii|csv|svg] [--scent-file <path>] [--schedule sequential|round-robin] <mission file>..."
+        ));
+    }
+    Ok(HeatmapOptions {
+        output,
+        missions,
+        scent_file,
+        schedule,
+    })
+}
+
+fn parse_replay_options(
+    args: impl Iterator<Item = String>,
+) -> Result<ReplayOptions, anyhow::Error> {
+    let args: Vec<String> = args.collect();
+    match &args[..] {
+        [log] if !log.starts_with("--") => Ok(ReplayOptions { log: log.into() }),
+        _ => Err(anyhow!("Usage: replay <replay log> < mission.txt")),
+    }
+}
+
+fn parse_debug_options(args: impl Iterator<Item = String>) -> Result<DebugOptions, anyhow::Error> {
+    let mut mission = None;
+    let mut schedule = Schedule::default();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--schedule" => schedule = Schedule::from_str(&flag_value(&arg, &mut args)?)?,
+            _ if arg.starts_with("--") => return Err(anyhow!("Unknown argument: {}", arg)),
+            _ if mission.is_none() => mission = Some(arg.into()),
+            _ => return Err(anyhow!("Unexpected argument: {}", arg)),
+        }
+    }
+
+    match mission {
+        Some(mission) => Ok(DebugOptions { mission, schedule }),
+        None => Err(anyhow!(
+            "Usage: debug <mission file> [--schedule sequential|round-robin]"
+        )),
+    }
+}
+
+fn parse_run_options(args: impl Iterator<Item = String>) -> Result<Options, anyhow::Error> {
+    let mut options = Options::default();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => options.output = OutputFormat::from_str(&flag_value(&arg, &mut args)?)?,
+            "--scent-store" => {
+                options.scent_store = ScentStoreKind::from_str(&flag_value(&arg, &mut args)?)?
+            }
+            "--scent-file" => options.scent_file = Some(flag_value(&arg, &mut args)?.into()),
+            "--schedule" => options.schedule = Schedule::from_str(&flag_value(&arg, &mut args)?)?,
+            "--stats" => options.stats = true,
+            "--replay-log" => options.replay_log = Some(flag_value(&arg, &mut args)?.into()),
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn flag_value(
+    flag: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, anyhow::Error> {
+    args.next().ok_or_else(|| anyhow!("{} needs a value", flag))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{
+        parse_args, AnalyzeOptions, Cli, CoverOptions, DebugCommand, DebugOptions, HeatmapFormat,
+        HeatmapOptions, LintOptions, OptimizeOptions, Options, OutputFormat, PlanOptions,
+        ReplayOptions, ScentStoreKind, SolveOptions, SolveQuery,
+    };
+    use crate::lint::Severity;
+    use crate::mission_instructions::RobotCommands;
+    use crate::planner::Goal;
+    use crate::robots::RobotPosition;
+    use crate::scents::ScentsAction;
+    use crate::simulation::Schedule;
+    use rstest::rstest;
+    use std::str::FromStr;
+
+    fn args(input: &str) -> Vec<String> {
+        input.split_whitespace().map(String::from).collect()
+    }
+
+    fn run_options(input: &str) -> Options {
+        match parse_args(args(input)).unwrap() {
+            Cli::Run(options) => options,
+            other => panic!("expected a run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_default_options() {
+        let options = run_options("");
+        assert_eq!(options.output, OutputFormat::Text);
+        assert_eq!(options.scent_file, None);
+        assert_eq!(options.schedule, Schedule::Sequential);
+        assert!(!options.stats);
+    }
+
+    #[test]
+    fn test_stats_flag() {
+        assert!(run_options("--output json --stats").stats);
+    }
+
+    #[test]
+    fn test_schedule_flag() {
+        let options = run_options("--schedule round-robin");
+        assert_eq!(options.schedule, Schedule::RoundRobin);
+        assert!(parse_args(args("--schedule parallel")).is_err());
+    }
+
+    #[test]
+    fn test_output_flag() {
+        let options = run_options("--output svg");
+        assert_eq!(options.output, OutputFormat::Svg);
+    }
+
+    #[test]
+    fn test_scent_flags() {
+        let options = run_options("--scent-store bitset --scent-file scents.txt");
+        assert_eq!(options.scent_store, ScentStoreKind::Bitset);
+        assert_eq!(options.scent_file, Some("scents.txt".into()));
+    }
+
+    #[test]
+    fn test_scents_subcommand() {
+        assert_eq!(
+            parse_args(args("scents merge all.txt a.txt b.txt")).unwrap(),
+            Cli::Scents(ScentsAction::Merge {
+                target: "all.txt".into(),
+                sources: vec!["a.txt".into(), "b.txt".into()],
+            })
+        );
+        assert_eq!(
+            parse_args(args("scents list all.txt")).unwrap(),
+            Cli::Scents(ScentsAction::List("all.txt".into()))
+        );
+        assert!(parse_args(args("scents merge all.txt")).is_err());
+        assert!(parse_args(args("scents wipe all.txt")).is_err());
+    }
+
+    #[test]
+    fn test_plan_subcommand() {
+        let plan = parse_args(
+            [
+                "plan",
+                "--from",
+                "1 1 E",
+                "--to",
+                "3 2",
+                "--scent-file",
+                "s.txt",
+            ]
+            .map(String::from),
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan,
+            Cli::Plan(PlanOptions {
+                from: RobotPosition::from_str("1 1 E").unwrap(),
+                to: Goal::from_str("3 2").unwrap(),
+                scent_file: Some("s.txt".into()),
+            })
+        );
+        assert!(parse_args(["plan", "--from", "1 1 E"].map(String::from)).is_err());
+        assert!(parse_args(["plan", "--from", "1 1", "--to", "3 2"].map(String::from)).is_err());
+    }
+
+    #[test]
+    fn test_optimize_subcommand() {
+        assert_eq!(
+            parse_args(args("optimize mission.txt --write")).unwrap(),
+            Cli::Optimize(OptimizeOptions {
+                mission: "mission.txt".into(),
+                write: true,
+            })
+        );
+        assert_eq!(
+            parse_args(args("optimize mission.txt")).unwrap(),
+            Cli::Optimize(OptimizeOptions {
+                mission: "mission.txt".into(),
+                write: false,
+            })
+        );
+        assert!(parse_args(args("optimize --write")).is_err());
+        assert!(parse_args(args("optimize a.txt b.txt")).is_err());
+        assert!(parse_args(args("optimize a.txt --dry-run")).is_err());
+    }
+
+    #[test]
+    fn test_analyze_subcommand() {
+        assert_eq!(
+            parse_args(args("analyze --output ascii --scent-file s.txt")).unwrap(),
+            Cli::Analyze(AnalyzeOptions {
+                output: OutputFormat::Ascii,
+                scent_file: Some("s.txt".into()),
+            })
+        );
+        assert_eq!(
+            parse_args(args("analyze")).unwrap(),
+            Cli::Analyze(AnalyzeOptions::default())
+        );
+        assert!(parse_args(args("analyze --schedule round-robin")).is_err());
+    }
+
+    #[test]
+    fn test_cover_subcommand() {
+        assert_eq!(
+            parse_args(args("cover --robots 4")).unwrap(),
+            Cli::Cover(CoverOptions { robots: 4 })
+        );
+        assert_eq!(
+            parse_args(args("cover")).unwrap(),
+            Cli::Cover(CoverOptions { robots: 1 })
+        );
+        assert!(parse_args(args("cover --robots many")).is_err());
+    }
+
+    #[test]
+    fn test_solve_subcommand() {
+        assert_eq!(
+            parse_args(["solve", "--commands", "FRl", "--to", "3 2 N"].map(String::from)).unwrap(),
+            Cli::Solve(SolveOptions {
+                commands: vec![
+                    RobotCommands::Forward,
+                    RobotCommands::Right,
+                    RobotCommands::HalfLeft
+                ],
+                query: SolveQuery::EndAt(Goal::from_str("3 2 N").unwrap()),
+                scent_file: None,
+            })
+        );
+        assert_eq!(
+            parse_args(args("solve --commands FF --lost --scent-file s.txt")).unwrap(),
+            Cli::Solve(SolveOptions {
+                commands: vec![RobotCommands::Forward, RobotCommands::Forward],
+                query: SolveQuery::Lost,
+                scent_file: Some("s.txt".into()),
+            })
+        );
+        assert!(parse_args(args("solve --commands FF")).is_err());
+        assert!(parse_args(args("solve --commands FX --lost")).is_err());
+        assert!(parse_args(args("solve --lost")).is_err());
+    }
+
+    #[test]
+    fn test_lint_subcommand() {
+        assert_eq!(
+            parse_args(args("lint --fail-on warning")).unwrap(),
+            Cli::Lint(LintOptions {
+                fail_on: Some(Severity::Warning)
+            })
+        );
+        assert_eq!(
+            parse_args(args("lint")).unwrap(),
+            Cli::Lint(LintOptions::default())
+        );
+        assert!(parse_args(args("lint --fail-on fatal")).is_err());
+    }
+
+    #[test]
+    fn test_bad_args() {
+        assert!(parse_args(args("--scent-file")).is_err());
+        assert!(parse_args(args("--scent-store redis")).is_err());
+        assert!(parse_args(args("--output")).is_err());
+        assert!(parse_args(args("--output pdf")).is_err());
+        assert!(parse_args(args("--verbose")).is_err());
+    }
+
+    #[test]
+    fn test_heatmap_subcommand() {
+        assert_eq!(
+            parse_args(args(
+                "heatmap --output csv a.txt --schedule round-robin --scent-file s.txt b.txt"
+            ))
+            .unwrap(),
+            Cli::Heatmap(HeatmapOptions {
+                output: HeatmapFormat::Csv,
+                missions: vec!["a.txt".into(), "b.txt".into()],
+                scent_file: Some("s.txt".into()),
+                schedule: Schedule::RoundRobin,
+            })
+        );
+        assert_eq!(
+            parse_args(args("heatmap a.txt")).unwrap(),
+            Cli::Heatmap(HeatmapOptions {
+                output: HeatmapFormat::Ascii,
+                missions: vec!["a.txt".into()],
+                scent_file: None,
+                schedule: Schedule::Sequential,
+            })
+        );
+        assert!(parse_args(args("heatmap")).is_err());
+        assert!(parse_args(args("heatmap --output json a.txt")).is_err());
+        assert!(parse_args(args("heatmap --stats a.txt")).is_err());
+    }
+
+    #[test]
+    fn test_replay_log_flag() {
+        assert_eq!(
+            parse_args(args("--replay-log run.log")).unwrap(),
+            Cli::Run(Options {
+                replay_log: Some("run.log".into()),
+                ..Options::default()
+            })
+        );
+        assert!(parse_args(args("--replay-log")).is_err());
+    }
+
+    #[test]
+    fn test_replay_subcommand() {
+        assert_eq!(
+            parse_args(args("replay run.log")).unwrap(),
+            Cli::Replay(ReplayOptions {
+                log: "run.log".into()
+            })
+        );
+        assert!(parse_args(args("replay")).is_err());
+        assert!(parse_args(args("replay a.log b.log")).is_err());
+        assert!(parse_args(args("replay --stats")).is_err());
+    }
+
+    #[test]
+    fn test_debug_subcommand() {
+        assert_eq!(
+            parse_args(args("debug mission.txt --schedule round-robin")).unwrap(),
+            Cli::Debug(DebugOptions {
+                mission: "mission.txt".into(),
+                schedule: Schedule::RoundRobin,
+            })
+        );
+        assert!(parse_args(args("debug")).is_err());
+        assert!(parse_args(args("debug a.txt b.txt")).is_err());
+    }
+
+    #[rstest]
+    #[case("", DebugCommand::Next(1))]
+    #[case("n", DebugCommand::Next(1))]
+    #[case("next 5", DebugCommand::Next(5))]
+    #[case("b", DebugCommand::Back(1))]
+    #[case("back 3", DebugCommand::Back(3))]
+    #[case("seek 0", DebugCommand::Seek(0))]
+    #[case("end", DebugCommand::End)]
+    #[case("quit", DebugCommand::Quit)]
+    fn test_debug_command(#[case] input: &str, #[case] expected: DebugCommand) {
+        assert_eq!(DebugCommand::from_str(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("seek")]
+    #[case("back -1")]
+    #[case("next 1 2")]
+    #[case("rewind")]
+    fn test_bad_debug_command(#[case] input: &str) {
+        assert!(DebugCommand::from_str(input).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::mission_instructions::{Command, Compass, Coordinates, Orientation, RobotCommands};
+use crate::parser::{writable_commands, MAX_ROBOT_COMMANDS};
+use crate::planner::{plan, Goal};
+use crate::plateau::{Obstacle, Plateau};
+use crate::robots::{Robot, RobotPosition, RobotStatus};
+use crate::scents::{KnownScents, HEADINGS};
+use anyhow::anyhow;
+use std::collections::HashSet;
+
+/// The squares a robot can drive to straight from `square`.
+fn neighbours(square: &Coordinates, plateau: &Plateau, compass: &Compass) -> Vec<Coordinates> {
+    let no_scents: HashSet<RobotPosition> = HashSet::new();
+    HEADINGS
+        .iter()
+        .filter(|heading| compass.supports_orientation(heading))
+        .filter_map(|heading| {
+            let position = RobotPosition {
+                coordinates: square.clone(),
+                orientation: heading.clone(),
+            };
+            let (next, status) =
+                RobotCommands::Forward.process(position, plateau, &mut KnownScents(&no_scents));
+            Some(next.coordinates).filter(|next| status == RobotStatus::Alive && next != square)
+        })
+        .collect()
+}
+
+/// Every open square of the plateau in the order a survey visits them: along
+/// the bottom row, back along the next one and so on up the grid. Ground that
+/// can't be driven to from the rest is surveyed separately, after it.
+fn survey_order(plateau: &Plateau, compass: &Compass) -> Vec<Coordinates> {
+    let bounds = &plateau.bounds;
+    let mut rows = vec![];
+    for (row, y) in (bounds.lower_left.y..=bounds.upper_right.y).enumerate() {
+        let mut squares: Vec<Coordinates> = (bounds.lower_left.x..=bounds.upper_right.x)
+            .map(|x| Coordinates { x, y })
+            .filter(|square| !plateau.off_plateau(square) && !plateau.is_blocked(square))
+            .collect();
+        if row % 2 == 1 {
+            squares.reverse();
+        }
+        rows.extend(squares);
+    }
+
+    let mut order = vec![];
+    let mut seen: HashSet<Coordinates> = HashSet::new();
+    for square in &rows {
+        if seen.contains(square) {
+            continue;
+        }
+        let mut patch: HashSet<Coordinates> = HashSet::new();
+        let mut to_visit = vec![square.clone()];
+        while let Some(next) = to_visit.pop() {
+            if patch.insert(next.clone()) {
+                to_visit.extend(neighbours(&next, plateau, compass));
+            }
+        }
+        order.extend(
+            rows.iter()
+                .filter(|square| patch.contains(*square))
+                .cloned(),
+        );
+        seen.extend(patch);
+    }
+    order
+}
+
+/// A robot being given its part of the survey.
+struct Surveyor {
+    start: RobotPosition,
+    at: RobotPosition,
+    commands: Vec<RobotCommands>,
+    spent: u32,
+}
+
+impl Surveyor {
+    /// A robot that only covers the square it is dropped on turns on the
+    /// spot, so that it has commands to write out.
+    fn into_robot(self) -> Robot {
+        let commands = if writable_commands(&self.commands) {
+            self.commands
+        } else {
+            vec![RobotCommands::Right]
+        };
+        Robot::new()
+            .set_start_position(self.start)
+            .update_commands(commands)
+    }
+}
+
+/// Splits the survey between as many robots as it takes when none of them
+/// may be given more than `limit` commands, or spend more than the mission's
+/// energy budget. Each robot stays parked where it finishes, so the robots
+/// after it are routed round that square.
+fn survey(command: &Command, order: &[Coordinates], limit: usize) -> Vec<Robot> {
+    let mut plateau = command.plateau();
+    let no_scents: HashSet<RobotPosition> = HashSet::new();
+    let route_to = |plateau: &Plateau, from: &RobotPosition, square: &Coordinates| {
+        plan(
+            plateau,
+            &command.compass,
+            from,
+            &Goal {
+                coordinates: square.clone(),
+                orientation: None,
+            },
+            &no_scents,
+            &command.energy_costs,
+        )
+    };
+    let mut covered: HashSet<Coordinates> = HashSet::new();
+    let mut robots = vec![];
+    let mut current: Option<Surveyor> = None;
+
+    for (index, square) in order.iter().enumerate() {
+        if covered.contains(square) {
+            continue;
+        }
+
+        if let Some(surveyor) = current.as_mut() {
+            let route = route_to(&plateau, &surveyor.at, square).filter(|route| {
+                let cost: u32 = route
+                    .iter()
+                    .map(|step| command.energy_costs.used(step, false))
+                    .sum();
+                surveyor.commands.len() + route.len() <= limit
+                    && command
+                        .energy
+                        .is_none_or(|budget| surveyor.spent + cost <= budget)
+            });
+            if let Some(route) = route {
+                for step in route {
+                    surveyor.spent += command.energy_costs.used(&step, false);
+                    surveyor.at = step
+                        .process(surveyor.at.clone(), &plateau, &mut KnownScents(&no_scents))
+                        .0;
+                    covered.insert(surveyor.at.coordinates.clone());
+                    surveyor.commands.push(step);
+                }
+                continue;
+            }
+            let surveyor = current.take().unwrap();
+            plateau.obstacles.push(Obstacle {
+                lower_left: surveyor.at.coordinates.clone(),
+                upper_right: surveyor.at.coordinates.clone(),
+            });
+            robots.push(surveyor.into_robot());
+        }
+
+        covered.insert(square.clone());
+        // face whichever way gets to the next square soonest
+        let next = order[index + 1..]
+            .iter()
+            .find(|next| !covered.contains(*next));
+        let heading = HEADINGS
+            .iter()
+            .filter(|heading| command.compass.supports_orientation(heading))
+            .min_by_key(|heading| {
+                let start = RobotPosition {
+                    coordinates: square.clone(),
+                    orientation: (*heading).clone(),
+                };
+                next.and_then(|next| route_to(&plateau, &start, next))
+                    .map_or(usize::MAX, |route| route.len())
+            })
+            .cloned()
+            .unwrap_or(Orientation::North);
+        let start = RobotPosition {
+            coordinates: square.clone(),
+            orientation: heading,
+        };
+        current = Some(Surveyor {
+            start: start.clone(),
+            at: start,
+            commands: vec![],
+            spent: 0,
+        });
+    }
+
+    robots.extend(current.map(Surveyor::into_robot));
+    robots
+}
+
+/// Plans a survey that drives over every open square of the mission's
+/// plateau using at most `robots` robots, without losing or crashing any of
+/// them. The commands are spread as evenly as the command limit allows.
+pub fn cover(command: &Command, robots: usize) -> Result<Vec<Robot>, anyhow::Error> {
+    if robots == 0 {
+        return Err(anyhow!("A survey needs at least one robot"));
+    }
+    let order = survey_order(&command.plateau(), &command.compass);
+
+    let mut best = survey(command, &order, MAX_ROBOT_COMMANDS);
+    if best.len() > robots {
+        return Err(anyhow!(
+            "Covering this plateau with at most {} commands a robot takes {} robots, not {}",
+            MAX_ROBOT_COMMANDS,
+            best.len(),
+            robots
+        ));
+    }
+
+    // the smallest limit that still fits in the robots given
+    let (mut too_few, mut enough) = (0, MAX_ROBOT_COMMANDS);
+    while too_few + 1 < enough {
+        let limit = (too_few + enough) / 2;
+        let survey = survey(command, &order, limit);
+        if survey.len() <= robots {
+            enough = limit;
+            best = survey;
+        } else {
+            too_few = limit;
+        }
+    }
+    Ok(best)
+}
+
+/// The survey as a mission: the plateau lines it was planned for followed by
+/// each robot's position and commands.
+pub fn mission_text(plateau_lines: &[&str], robots: &[Robot]) -> String {
+    let mut output = String::new();
+    for line in plateau_lines {
+        output.push_str(&format!("{}\n", line));
+    }
+    for (index, robot) in robots.iter().enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "{}\n{}\n",
+            robot.position,
+            robot
+                .robot_commands
+                .iter()
+                .map(|command| command.to_string())
+                .collect::<String>()
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use crate::coverage::{cover, mission_text};
+    use crate::mission_instructions::Coordinates;
+    use crate::parser::{parse_input_to_command, plateau_lines, MAX_ROBOT_COMMANDS};
+    use crate::robots::{RobotPosition, RobotStatus};
+    use crate::scents::ScentLog;
+    use crate::simulation::{run_mission, Schedule};
+    use rstest::rstest;
+    use std::collections::HashSet;
+
+    /// Plans the survey, writes it out as a mission, reads it back in and
+    /// checks that running it covers every open square without a mishap.
+    fn check_survey(mission: &str, robots: usize) -> usize {
+        let command = parse_input_to_command(mission).unwrap();
+        let survey = cover(&command, robots).unwrap();
+        let text = mission_text(&plateau_lines(mission), &survey);
+        let surveyed = parse_input_to_command(&text).unwrap();
+        assert!(surveyed.robots.len() <= robots);
+
+        let plateau = surveyed.plateau();
+        let mut visited: HashSet<Coordinates> = HashSet::new();
+        let mut scents: HashSet<RobotPosition> = HashSet::new();
+        for robot in &surveyed.robots {
+            assert!(robot.robot_commands.len() <= MAX_ROBOT_COMMANDS);
+            let mut position = robot.position.clone();
+            visited.insert(position.coordinates.clone());
+            for step in &robot.robot_commands {
+                position = step.process(position, &plateau, &mut scents).0;
+                visited.insert(position.coordinates.clone());
+            }
+        }
+        let bounds = &plateau.bounds;
+        for y in bounds.lower_left.y..=bounds.upper_right.y {
+            for x in bounds.lower_left.x..=bounds.upper_right.x {
+                let square = Coordinates { x, y };
+                if !plateau.off_plateau(&square) && !plateau.is_blocked(&square) {
+                    assert!(visited.contains(&square), "{} {} not covered", x, y);
+                }
+            }
+        }
+
+        let mut scent_log = ScentLog::new(HashSet::<RobotPosition>::new(), None);
+        let outcome = run_mission(&surveyed, Schedule::Sequential, &mut scent_log);
+        assert!(outcome
+            .robots
+            .iter()
+            .all(|robot| robot.robot_status == RobotStatus::Alive));
+        assert_eq!(outcome.collisions, vec![]);
+        surveyed.robots.len()
+    }
+
+    #[rstest]
+    #[case("5 3\n", 1, 1)]
+    #[case("5 3\n", 3, 3)]
+    #[case("obstacle 1 0 1 2\n5 3\n", 2, 2)]
+    #[case("polygon 0 0 4 0 4 4\n4 4\n", 1, 1)]
+    #[case("mask #.#\nmask #.#\n2 1\n", 2, 2)]
+    #[case("compass 8\non-collision block\n9 9\n", 2, 2)]
+    #[case("energy 10\n5 3\n", 4, 4)]
+    #[case("20 20\n", 6, 6)]
+    #[case("1 0\n", 5, 1)]
+    fn test_cover(#[case] mission: &str, #[case] robots: usize, #[case] expected: usize) {
+        assert_eq!(check_survey(mission, robots), expected);
+    }
+
+    #[test]
+    fn test_cover_needs_enough_robots() {
+        let command = parse_input_to_command("20 20\n").unwrap();
+
+        assert!(cover(&command, 1).is_err());
+        assert!(cover(&command, 0).is_err());
+    }
+
+    #[test]
+    fn test_mission_text() {
+        let command = parse_input_to_command("boundary clamp\n2 0\n").unwrap();
+        let survey = cover(&command, 1).unwrap();
+
+        assert_eq!(
+            mission_text(&plateau_lines("boundary clamp\n2 0\n0 0 N\nF\n"), &survey),
+            "boundary clamp\n2 0\n0 0 E\nFF\n"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::mission_instructions::RobotCommands;
+use anyhow::anyhow;
+use std::str::FromStr;
+
+/// How much energy each kind of command uses up. A forward move that doesn't
+/// get anywhere, because of an obstacle, a scent, the edge or another robot,
+/// costs `blocked` instead of `forward`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct EnergyCosts {
+    pub forward: u32,
+    pub turn: u32,
+    pub blocked: u32,
+}
+
+impl Default for EnergyCosts {
+    fn default() -> Self {
+        EnergyCosts {
+            forward: 1,
+            turn: 1,
+            blocked: 1,
+        }
+    }
+}
+
+impl EnergyCosts {
+    /// Sets one cost from a `kind amount` pair, e.g. `forward 2`.
+    pub fn set(&mut self, input: &str) -> Result<(), anyhow::Error> {
+        let (kind, amount) = input
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Cost needs a command and an amount, e.g. forward 2"))?;
+        let amount = u32::from_str(amount.trim())?;
+        match kind {
+            "forward" => self.forward = amount,
+            "turn" => self.turn = amount,
+            "blocked" => self.blocked = amount,
+            _ => {
+                return Err(anyhow!(
+                    "Cost must be for forward, turn or blocked, got {}",
+                    kind
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// What `command` uses up, where `blocked` is whether a forward move
+    /// doesn't get anywhere.
+    pub fn used(&self, command: &RobotCommands, blocked: bool) -> u32 {
+        match command {
+            RobotCommands::Forward if blocked => self.blocked,
+            RobotCommands::Forward => self.forward,
+            _ => self.turn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::energy::EnergyCosts;
+    use crate::mission_instructions::RobotCommands;
+    use rstest::*;
+
+    #[test]
+    fn test_set_costs() {
+        let mut costs = EnergyCosts::default();
+        costs.set("forward 3").unwrap();
+        costs.set("blocked 5").unwrap();
+
+        assert_eq!(
+            costs,
+            EnergyCosts {
+                forward: 3,
+                turn: 1,
+                blocked: 5,
+            }
+        );
+        assert!(costs.set("reverse 2").is_err());
+        assert!(costs.set("turn").is_err());
+        assert!(costs.set("turn -1").is_err());
+    }
+
+    #[rstest]
+    #[case(RobotCommands::Forward, false, 3)]
+    #[case(RobotCommands::Forward, true, 5)]
+    #[case(RobotCommands::Left, false, 2)]
+    #[case(RobotCommands::HalfRight, false, 2)]
+    fn test_used(#[case] command: RobotCommands, #[case] blocked: bool, #[case] expected: u32) {
+        let costs = EnergyCosts {
+            forward: 3,
+            turn: 2,
+            blocked: 5,
+        };
+
+        assert_eq!(costs.used(&command, blocked), expected);
+    }
+}
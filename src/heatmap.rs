@@ -0,0 +1,149 @@
+use crate::mission_instructions::{Command, Coordinates, GridBounds};
+use crate::robots::RobotStatus;
+use crate::simulation::MissionOutcome;
+use crate::solver::Edge;
+use std::collections::BTreeMap;
+
+/// Where robots went and where they were lost, added up over any number of
+/// missions. The grid grows to cover every mission added to it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Heatmap {
+    pub bounds: Option<GridBounds>,
+    /// Times a robot stood on each square, counting the square it was
+    /// dropped on, keyed by (x, y).
+    pub visits: BTreeMap<(i32, i32), usize>,
+    /// Robots lost from each square over each edge.
+    pub losses: BTreeMap<((i32, i32), Edge), usize>,
+    pub missions: usize,
+}
+
+impl Heatmap {
+    pub fn add(&mut self, command: &Command, outcome: &MissionOutcome) {
+        let bounds = command.bounds();
+        self.bounds = Some(match self.bounds.take() {
+            Some(known) => GridBounds {
+                lower_left: Coordinates {
+                    x: known.lower_left.x.min(bounds.lower_left.x),
+                    y: known.lower_left.y.min(bounds.lower_left.y),
+                },
+                upper_right: Coordinates {
+                    x: known.upper_right.x.max(bounds.upper_right.x),
+                    y: known.upper_right.y.max(bounds.upper_right.y),
+                },
+            },
+            None => bounds,
+        });
+
+        for square in outcome.paths.iter().flatten() {
+            *self.visits.entry((square.x, square.y)).or_default() += 1;
+        }
+        let plateau = command.plateau();
+        for robot in &outcome.robots {
+            if robot.robot_status == RobotStatus::Lost {
+                let square = &robot.position.coordinates;
+                let edge = Edge::crossed(&robot.position, &plateau);
+                *self.losses.entry(((square.x, square.y), edge)).or_default() += 1;
+            }
+        }
+        self.missions += 1;
+    }
+
+    pub fn visits_at(&self, square: &Coordinates) -> usize {
+        self.visits
+            .get(&(square.x, square.y))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn most_visits(&self) -> usize {
+        self.visits.values().copied().max().unwrap_or_default()
+    }
+
+    /// The losses from one square, by edge.
+    pub fn losses_at<'a>(
+        &'a self,
+        square: &'a Coordinates,
+    ) -> impl Iterator<Item = (Edge, usize)> + 'a {
+        self.losses
+            .iter()
+            .filter(move |(((x, y), _), _)| *x == square.x && *y == square.y)
+            .map(|((_, edge), lost)| (*edge, *lost))
+    }
+
+    /// One row per square, bottom row first, with its visits, its losses and
+    /// the edges they went over, e.g. `north:2;east:1`.
+    pub fn csv(&self) -> String {
+        let mut output = String::from("x,y,visits,lost,edges\n");
+        let Some(bounds) = &self.bounds else {
+            return output;
+        };
+        for y in bounds.lower_left.y..=bounds.upper_right.y {
+            for x in bounds.lower_left.x..=bounds.upper_right.x {
+                let square = Coordinates { x, y };
+                let losses: Vec<(Edge, usize)> = self.losses_at(&square).collect();
+                output.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    x,
+                    y,
+                    self.visits_at(&square),
+                    losses.iter().map(|(_, lost)| lost).sum::<usize>(),
+                    losses
+                        .iter()
+                        .map(|(edge, lost)| format!("{}:{}", edge.key(), lost))
+                        .collect::<Vec<String>>()
+                        .join(";")
+                ));
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::heatmap::Heatmap;
+    use crate::mission_instructions::Coordinates;
+    use crate::parser::parse_input_to_command;
+    use crate::scents::ScentLog;
+    use crate::simulation::{run_mission, Schedule};
+    use crate::solver::Edge;
+    use std::collections::HashSet;
+
+    fn heatmap(missions: &[&str]) -> Heatmap {
+        let mut heatmap = Heatmap::default();
+        for mission in missions {
+            let command = parse_input_to_command(mission).unwrap();
+            let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+            let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+            heatmap.add(&command, &outcome);
+        }
+        heatmap
+    }
+
+    #[test]
+    fn test_heatmap_adds_up_missions() {
+        let heatmap = heatmap(&["2 1\n0 0 E\nFF\n\n2 1 N\nF", "3 1\n0 0 N\nF\n\n2 1 N\nF"]);
+
+        assert_eq!(heatmap.missions, 2);
+        assert_eq!(heatmap.bounds.as_ref().unwrap().upper_right.x, 3);
+        assert_eq!(heatmap.visits_at(&Coordinates { x: 0, y: 0 }), 2);
+        assert_eq!(heatmap.visits_at(&Coordinates { x: 2, y: 1 }), 2);
+        assert_eq!(heatmap.most_visits(), 2);
+        assert_eq!(
+            heatmap
+                .losses_at(&Coordinates { x: 2, y: 1 })
+                .collect::<Vec<(Edge, usize)>>(),
+            vec![(Edge::North, 2)]
+        );
+    }
+
+    #[test]
+    fn test_heatmap_csv() {
+        let heatmap = heatmap(&["1 0\n0 0 E\nFF\n\n0 0 S\nF"]);
+
+        assert_eq!(
+            heatmap.csv(),
+            "x,y,visits,lost,edges\n0,0,2,1,south:1\n1,0,1,1,east:1\n"
+        );
+    }
+}
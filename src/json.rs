@@ -0,0 +1,138 @@
+use std::fmt;
+
+/// Just enough of a JSON value to print reports without pulling in serde.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<K: Into<String>>(fields: Vec<(K, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string<S: fmt::Display>(value: S) -> Self {
+        Json::String(value.to_string())
+    }
+
+    /// Adds a field to the end of an object. Anything else is left as it is.
+    pub fn push<K: Into<String>>(&mut self, key: K, value: Json) {
+        if let Json::Object(fields) = self {
+            fields.push((key.into(), value));
+        }
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let pad = "  ".repeat(indent + 1);
+        let closing_pad = "  ".repeat(indent);
+        match self {
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write_escaped(f, value),
+            Json::Array(values) if values.is_empty() => write!(f, "[]"),
+            Json::Array(values) => {
+                writeln!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    write!(f, "{}", pad)?;
+                    value.write_indented(f, indent + 1)?;
+                    if index + 1 < values.len() {
+                        write!(f, ",")?;
+                    }
+                    writeln!(f)?;
+                }
+                write!(f, "{}]", closing_pad)
+            }
+            Json::Object(fields) if fields.is_empty() => write!(f, "{{}}"),
+            Json::Object(fields) => {
+                writeln!(f, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    write!(f, "{}", pad)?;
+                    write_escaped(f, key)?;
+                    write!(f, ": ")?;
+                    value.write_indented(f, indent + 1)?;
+                    if index + 1 < fields.len() {
+                        write!(f, ",")?;
+                    }
+                    writeln!(f)?;
+                }
+                write!(f, "{}}}", closing_pad)
+            }
+        }
+    }
+}
+
+impl From<i32> for Json {
+    fn from(value: i32) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::json::Json;
+
+    #[test]
+    fn test_json_output() {
+        let value = Json::object(vec![
+            ("name", Json::string("rover \"7\"")),
+            ("x", Json::from(3)),
+            ("alive", Json::from(true)),
+            ("tags", Json::Array(vec![])),
+            (
+                "scents",
+                Json::Array(vec![Json::from(1), Json::from(false)]),
+            ),
+        ]);
+
+        insta::assert_display_snapshot!(value)
+    }
+}
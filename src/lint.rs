@@ -0,0 +1,258 @@
+use crate::mission_instructions::{Command, RobotCommands};
+use crate::optimizer::wasted_turns;
+use crate::plateau::BoundaryPolicy;
+use crate::robots::{RobotPosition, RobotStatus};
+use crate::scents::{Scent, ScentLog};
+use crate::simulation::{run_mission, Schedule};
+use anyhow::anyhow;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// How much a lint finding matters, least first.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(anyhow!(
+                "Severity must be info, warning or error, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Something in a mission that is legal but most likely a mistake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// The robot's line in the mission, as in the rest of the reports.
+    pub line: usize,
+    pub severity: Severity,
+    pub robot: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}: {}", self.line, self.severity, self.message)
+    }
+}
+
+fn commands_string(commands: &[RobotCommands]) -> String {
+    commands.iter().map(|command| command.to_string()).collect()
+}
+
+/// Runs the mission sequentially and points out robots that are lost on
+/// their first forward move, commands that never run because the robot has
+/// already stopped, turns that could be done with fewer and robots dropped
+/// facing off the plateau over a scent that will stop them.
+pub fn lint(command: &Command) -> Vec<Finding> {
+    let plateau = command.plateau();
+    let mut scent_log = ScentLog::new(HashSet::<RobotPosition>::new(), command.scent_decay);
+    let outcome = run_mission(command, Schedule::Sequential, &mut scent_log);
+    let mut scents = scent_log.active();
+    scents.extend(scent_log.expired());
+    let mut findings = vec![];
+
+    for (index, (robot, finished)) in command.robots.iter().zip(&outcome.robots).enumerate() {
+        let label = robot.label(index);
+        let mut finding = |severity: Severity, message: String| {
+            findings.push(Finding {
+                line: robot.line,
+                severity,
+                robot: index,
+                message,
+            })
+        };
+        let commands = &robot.robot_commands;
+        let commands_run = commands.len() - finished.robot_commands.len();
+
+        let first_forward = commands
+            .iter()
+            .position(|command| *command == RobotCommands::Forward);
+        if finished.robot_status == RobotStatus::Lost && first_forward == Some(commands_run - 1) {
+            finding(
+                Severity::Error,
+                format!(
+                    "robot {} is lost on its first forward move (command {}) from {}",
+                    label, commands_run, finished.position
+                ),
+            );
+        }
+
+        if finished.robot_status != RobotStatus::Alive && !finished.robot_commands.is_empty() {
+            finding(
+                Severity::Warning,
+                format!(
+                    "robot {} is {} after command {}, so the {} commands after it never run",
+                    label,
+                    finished.robot_status.to_string().to_lowercase(),
+                    commands_run,
+                    finished.robot_commands.len()
+                ),
+            );
+        }
+
+        for wasted in wasted_turns(commands, &command.compass, robot.model.as_ref()) {
+            let shortest = if wasted.shortest.is_empty() {
+                "no turn at all".to_string()
+            } else {
+                commands_string(&wasted.shortest)
+            };
+            finding(
+                Severity::Info,
+                format!(
+                    "robot {} turns {} at commands {}-{}, which is the same as {}",
+                    label,
+                    commands_string(&wasted.turns),
+                    wasted.at + 1,
+                    wasted.at + wasted.turns.len(),
+                    shortest
+                ),
+            );
+        }
+
+        let scent_aware = robot.model.as_ref().is_none_or(|model| model.scent_aware);
+        let faces_off = plateau.boundary == BoundaryPolicy::Lost
+            && plateau.off_plateau(&robot.position.move_forward().coordinates);
+        if scent_aware && faces_off {
+            let earlier: Vec<&Scent> = scents
+                .iter()
+                .filter(|scent| scent.laid.robot < index)
+                .collect();
+            let positions: HashSet<RobotPosition> =
+                earlier.iter().map(|scent| scent.position.clone()).collect();
+            let laid_by = plateau
+                .scent_policy
+                .protecting_scent(&robot.position, &plateau, &positions)
+                .and_then(|position| earlier.iter().find(|scent| scent.position == position))
+                .map(|scent| scent.laid.robot);
+            if let Some(laid_by) = laid_by {
+                finding(
+                    Severity::Warning,
+                    format!(
+                        "robot {} is dropped at {} facing off the plateau over robot {}'s scent, so its forward moves from there are ignored",
+                        label,
+                        robot.position,
+                        command.robots[laid_by].label(laid_by)
+                    ),
+                );
+            }
+        }
+    }
+
+    findings.sort_by_key(|finding| finding.line);
+    findings
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lint::{lint, Severity};
+    use crate::parser::parse_input_to_command;
+    use rstest::rstest;
+    use std::str::FromStr;
+
+    fn findings(mission: &str) -> Vec<String> {
+        let command = parse_input_to_command(mission).unwrap();
+        lint(&command)
+            .iter()
+            .map(|finding| finding.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_mission() {
+        assert_eq!(
+            findings("5 3\n1 1 E\nRFRFRFRF\n\n2 2 N\nFLF"),
+            vec![] as Vec<String>
+        );
+    }
+
+    #[test]
+    fn test_lost_on_first_forward() {
+        assert_eq!(
+            findings("5 3\n1 1 E\nF\n\n3 3 N\nLRFRF"),
+            vec![
+                "line 6: error: robot 1 is lost on its first forward move (command 3) from 3 3 N",
+                "line 6: warning: robot 1 is lost after command 3, so the 2 commands after it never run",
+                "line 6: info: robot 1 turns LR at commands 1-2, which is the same as no turn at all",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commands_after_a_crash() {
+        assert_eq!(
+            findings("obstacle 2 1\non-obstacle crash\n5 3\n1 1 E\nFFF"),
+            vec!["line 5: warning: robot 0 is crashed after command 1, so the 2 commands after it never run"]
+        );
+    }
+
+    #[test]
+    fn test_wasted_turns() {
+        assert_eq!(
+            findings("5 3\n1 1 E\nRRRF"),
+            vec!["line 3: info: robot 0 turns RRR at commands 1-3, which is the same as L"]
+        );
+    }
+
+    #[test]
+    fn test_wasted_turns_follow_the_robot_model() {
+        assert_eq!(
+            findings("model righty commands=FR\n5 3\n@a model=righty\n1 1 E\nRRRFRRRRRF"),
+            vec!["line 5: info: robot a turns RRRRR at commands 5-9, which is the same as R"]
+        );
+    }
+
+    #[test]
+    fn test_dropped_over_a_scent() {
+        assert_eq!(
+            findings("5 3\n@scout\n3 3 N\nF\n\n3 3 N\nFRF"),
+            vec![
+                "line 4: error: robot scout is lost on its first forward move (command 1) from 3 3 N",
+                "line 7: warning: robot 1 is dropped at 3 3 N facing off the plateau over robot scout's scent, so its forward moves from there are ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_scents_from_earlier_robots_are_named() {
+        // robot 1's scent is laid after robot 0 has finished
+        assert_eq!(
+            findings("5 3\n3 3 N\nL\n\n@scout\n3 3 N\nF"),
+            vec![
+                "line 7: error: robot scout is lost on its first forward move (command 1) from 3 3 N",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("info", Severity::Info)]
+    #[case("warning", Severity::Warning)]
+    #[case("error", Severity::Error)]
+    fn test_severity(#[case] input: &str, #[case] expected: Severity) {
+        assert_eq!(Severity::from_str(input).unwrap(), expected);
+        assert!(Severity::Error > Severity::Warning && Severity::Warning > Severity::Info);
+    }
+}
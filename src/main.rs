@@ -1,16 +1,99 @@
+mod analysis;
+mod cli;
+mod coverage;
+mod energy;
+mod heatmap;
+mod json;
+mod lint;
 mod mission_instructions;
+mod models;
+mod optimizer;
 mod parser;
+mod planner;
+mod plateau;
+mod render;
+mod replay;
+mod report;
 mod robots;
+mod scents;
+mod simulation;
+mod solver;
+mod stats;
 
+use crate::cli::{
+    parse_args, AnalyzeOptions, Cli, CoverOptions, DebugCommand, DebugOptions, HeatmapFormat,
+    HeatmapOptions, LintOptions, OptimizeOptions, Options, OutputFormat, PlanOptions,
+    ReplayOptions, ScentStoreKind, SolveOptions, SolveQuery,
+};
+use crate::mission_instructions::Command;
 use crate::parser::parse_input_to_command;
+use crate::report::MissionReport;
 use crate::robots::RobotPosition;
+use crate::scents::{
+    run_scents_action, BitsetScentStore, PersistentScentStore, ScentLog, ScentStore,
+};
+use crate::simulation::run_mission;
+use crate::stats::MissionStats;
 use anyhow::anyhow;
 use std::collections::HashSet;
 use std::error;
+use std::fs;
 use std::io::{self, Read};
-use std::str;
+use std::path::PathBuf;
+use std::{env, str};
 
-fn main() -> Result<(), Box<dyn error::Error>> {
+/// Runs the mission against the chosen store, wrapping it in a scent file
+/// when one was given so scents carry over between runs.
+fn run_with_store<'a, S: ScentStore>(
+    input: &str,
+    command: &'a Command,
+    options: &Options,
+    scent_tracker: S,
+) -> Result<MissionReport<'a>, anyhow::Error> {
+    match &options.scent_file {
+        Some(path) => {
+            let scent_tracker = PersistentScentStore::open(path, &command.bounds(), scent_tracker)?;
+            let mut scent_log = ScentLog::new(scent_tracker, command.scent_decay);
+            let known = scent_log.inner().scents();
+            let outcome = run_mission(command, options.schedule, &mut scent_log);
+            write_replay_log(input, options, known, &outcome)?;
+            scent_log.save()?;
+            Ok(MissionReport::new(command, outcome, &scent_log))
+        }
+        None => {
+            let mut scent_log = ScentLog::new(scent_tracker, command.scent_decay);
+            let known = scent_log.inner().scents();
+            let outcome = run_mission(command, options.schedule, &mut scent_log);
+            write_replay_log(input, options, known, &outcome)?;
+            Ok(MissionReport::new(command, outcome, &scent_log))
+        }
+    }
+}
+
+fn write_replay_log(
+    input: &str,
+    options: &Options,
+    known: Vec<RobotPosition>,
+    outcome: &simulation::MissionOutcome,
+) -> Result<(), anyhow::Error> {
+    match &options.replay_log {
+        Some(path) => replay::ReplayLog::new(
+            input,
+            options.schedule,
+            options.scent_store,
+            known,
+            outcome.steps.clone(),
+        )
+        .write(path),
+        None => Ok(()),
+    }
+}
+
+fn read_mission() -> Result<Command, Box<dyn error::Error>> {
+    parse_input_to_command(&read_input()?)
+}
+
+fn read_input() -> Result<String, Box<dyn error::Error>> {
     let mut buffer = vec![];
     let stdin = io::stdin();
     let mut handle = stdin.lock();
@@ -21,13 +104,331 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         Err(e) => Err(anyhow!("Invalid UTF-8 sequence: {}", e)),
     }?;
 
-    let command = parse_input_to_command(string)?;
+    Ok(string.to_string())
+}
+
+/// The scents in the scent file, if one was given.
+fn known_scents(
+    command: &Command,
+    scent_file: &Option<PathBuf>,
+) -> Result<HashSet<RobotPosition>, anyhow::Error> {
+    let scent_tracker: HashSet<RobotPosition> = HashSet::new();
+    let scents = match scent_file {
+        Some(path) => PersistentScentStore::open(path, &command.bounds(), scent_tracker)?.scents(),
+        None => vec![],
+    };
+    Ok(scents.into_iter().collect())
+}
+
+/// Prints the cheapest safe commands between the two positions on the
+/// mission's plateau.
+fn run_plan(options: &PlanOptions) -> Result<(), Box<dyn error::Error>> {
+    let command = read_mission()?;
+    let plateau = command.plateau();
+    for coordinates in [&options.from.coordinates, &options.to.coordinates] {
+        if plateau.off_plateau(coordinates) || plateau.is_blocked(coordinates) {
+            return Err(anyhow!(
+                "{} {} isn't open ground on this plateau",
+                coordinates.x,
+                coordinates.y
+            )
+            .into());
+        }
+    }
+
+    let scents = known_scents(&command, &options.scent_file)?;
+
+    let route = planner::plan(
+        &plateau,
+        &command.compass,
+        &options.from,
+        &options.to,
+        &scents,
+        &command.energy_costs,
+    )
+    .ok_or_else(|| anyhow!("No safe route from {} to {}", options.from, options.to))?;
+    println!(
+        "{}",
+        route
+            .iter()
+            .map(|command| command.to_string())
+            .collect::<String>()
+    );
+    Ok(())
+}
+
+/// Prints how dangerous a forward move is from every state on the mission's
+/// plateau.
+fn run_analyze(options: &AnalyzeOptions) -> Result<(), Box<dyn error::Error>> {
+    let command = read_mission()?;
+    let scents = known_scents(&command, &options.scent_file)?;
+    let map = analysis::analyze(&command.plateau(), &command.compass, &scents);
+
+    match options.output {
+        OutputFormat::Text => print!("{}", map.text()),
+        OutputFormat::Json => println!("{}", map.to_json()),
+        OutputFormat::Ascii => print!("{}", render::danger_ascii(&map)),
+        OutputFormat::Svg => print!("{}", render::danger_svg(&map)),
+    }
+    Ok(())
+}
+
+/// Prints a mission that surveys every open square of the plateau read from
+/// stdin.
+fn run_cover(options: &CoverOptions) -> Result<(), Box<dyn error::Error>> {
+    let input = read_input()?;
+    let command = parse_input_to_command(&input)?;
+    let survey = coverage::cover(&command, options.robots)?;
+    print!(
+        "{}",
+        coverage::mission_text(&parser::plateau_lines(&input), &survey)
+    );
+    Ok(())
+}
+
+/// Prints the starts from which the commands end at the goal, or lose the
+/// robot, on the mission's plateau.
+fn run_solve(options: &SolveOptions) -> Result<(), Box<dyn error::Error>> {
+    let command = read_mission()?;
+    if options
+        .commands
+        .iter()
+        .any(|robot_command| !command.compass.supports_command(robot_command))
+    {
+        return Err(anyhow!("Half-turn commands need the 8-way compass (compass 8)").into());
+    }
+    let scents = known_scents(&command, &options.scent_file)?;
+    let plateau = command.plateau();
+    let outcomes =
+        solver::run_from_every_start(&plateau, &command.compass, &options.commands, &scents);
+
+    match &options.query {
+        SolveQuery::EndAt(goal) => {
+            let starts: Vec<&solver::Outcome> = solver::arriving_at(&outcomes, goal).collect();
+            for outcome in &starts {
+                println!("{}", outcome.start);
+            }
+            println!("{} starts end alive at {}", starts.len(), goal);
+        }
+        SolveQuery::Lost => {
+            let mut lost = 0;
+            for outcome in &outcomes {
+                if let Some(edge) = outcome.edge(&plateau) {
+                    lost += 1;
+                    println!(
+                        "{}: lost from {} on command {}, over the {}",
+                        outcome.start, outcome.end, outcome.commands_run, edge
+                    );
+                }
+            }
+            println!("{} of {} starts lose the robot", lost, outcomes.len());
+        }
+    }
+    Ok(())
+}
+
+/// Prints everything suspicious about the mission on stdin, failing when a
+/// finding is as severe as `--fail-on` asks.
+fn run_lint(options: &LintOptions) -> Result<(), Box<dyn error::Error>> {
+    let command = read_mission()?;
+    let findings = lint::lint(&command);
+    for finding in &findings {
+        println!("{}", finding);
+    }
+
+    if let Some(fail_on) = options.fail_on {
+        let failed = findings
+            .iter()
+            .filter(|finding| finding.severity >= fail_on)
+            .count();
+        if failed > 0 {
+            return Err(anyhow!("{} findings at {} or above", failed, fail_on).into());
+        }
+    }
+    Ok(())
+}
+
+/// Prints how much shorter each robot's commands can get without changing
+/// what the mission does, and writes them back to the file when asked to.
+fn run_optimize(options: &OptimizeOptions) -> Result<(), Box<dyn error::Error>> {
+    let input = fs::read_to_string(&options.mission)?;
+    let command = parse_input_to_command(&input)?;
+    let (_, savings) = optimizer::optimize(&command);
+
+    let format = |commands: &[mission_instructions::RobotCommands]| {
+        commands
+            .iter()
+            .map(|command| command.to_string())
+            .collect::<String>()
+    };
+    for saving in &savings {
+        println!(
+            "robot {} (line {}): {} -> {} ({} saved)",
+            saving.label,
+            saving.line,
+            format(&saving.before),
+            format(&saving.after),
+            saving.saved()
+        );
+    }
+    let total: usize = savings.iter().map(|saving| saving.saved()).sum();
+    if savings.is_empty() {
+        println!("Nothing to optimize");
+    } else if options.write {
+        fs::write(&options.mission, optimizer::rewrite(&input, &savings))?;
+        println!(
+            "{} commands saved, rewrote {}",
+            total,
+            options.mission.display()
+        );
+    } else {
+        println!(
+            "{} commands saved, run again with --write to rewrite {}",
+            total,
+            options.mission.display()
+        );
+    }
+    Ok(())
+}
+
+/// Runs each mission file on its own, with no scents carried between them,
+/// and prints where the robots went and were lost across all of them.
+fn run_heatmap(options: &HeatmapOptions) -> Result<(), Box<dyn error::Error>> {
+    let mut heatmap = heatmap::Heatmap::default();
+    for mission in &options.missions {
+        let input = fs::read_to_string(mission)
+            .map_err(|error| anyhow!("Could not read {}: {}", mission.display(), error))?;
+        let command = parse_input_to_command(&input)
+            .map_err(|error| anyhow!("{}: {}", mission.display(), error))?;
+        let scents = known_scents(&command, &options.scent_file)
+            .map_err(|error| anyhow!("{}: {}", mission.display(), error))?;
+        let mut scent_log = ScentLog::new(scents, command.scent_decay);
+        let outcome = run_mission(&command, options.schedule, &mut scent_log);
+        heatmap.add(&command, &outcome);
+    }
+
+    match options.output {
+        HeatmapFormat::Ascii => print!("{}", render::heatmap_ascii(&heatmap)),
+        HeatmapFormat::Csv => print!("{}", heatmap.csv()),
+        HeatmapFormat::Svg => print!("{}", render::heatmap_svg(&heatmap)),
+    }
+    Ok(())
+}
+
+/// Runs the mission on stdin again the way the log says it went and reports
+/// the first tick that turned out differently.
+fn run_replay(options: &ReplayOptions) -> Result<(), Box<dyn error::Error>> {
+    let log = replay::ReplayLog::read(&options.log)?;
+    let input = read_input()?;
+    let outcome = log.verify(&input)?;
+    println!("{}", outcome);
+    match outcome.divergence {
+        None => Ok(()),
+        Some(_) => Err(anyhow!("Replay diverged from {}", options.log.display()).into()),
+    }
+}
+
+fn print_debug_state<S: ScentStore + Clone>(mission: &simulation::Rewindable<S>) {
+    println!("tick {}", mission.tick());
+    for (index, robot) in mission.robots().iter().enumerate() {
+        println!(
+            "  {}: {} {}",
+            robot.label(index),
+            robot.position,
+            robot.robot_status
+        );
+    }
+    if let Some(step) = mission.last_step() {
+        println!("  last: {}", step);
+    }
+    if mission.is_finished() {
+        println!("  mission over");
+    }
+}
+
+/// Steps through the mission file with the commands typed on stdin, going
+/// backwards as well as forwards.
+fn run_debug(options: &DebugOptions) -> Result<(), Box<dyn error::Error>> {
+    let input = fs::read_to_string(&options.mission)?;
+    let command = parse_input_to_command(&input)?;
+    let mut scent_log = ScentLog::new(HashSet::<RobotPosition>::new(), command.scent_decay);
+    let mut mission = simulation::Rewindable::new(
+        &command,
+        options.schedule,
+        &mut scent_log,
+        simulation::SNAPSHOT_INTERVAL,
+    );
+
+    print_debug_state(&mission);
+    for line in io::stdin().lines() {
+        match line?.parse::<DebugCommand>() {
+            Ok(DebugCommand::Next(ticks)) => mission.seek(mission.tick() + ticks),
+            Ok(DebugCommand::Back(ticks)) => mission.rewind(ticks),
+            Ok(DebugCommand::Seek(tick)) => mission.seek(tick),
+            Ok(DebugCommand::End) => while mission.step() {},
+            Ok(DebugCommand::Quit) => break,
+            Err(error) => {
+                println!("{}", error);
+                continue;
+            }
+        }
+        print_debug_state(&mission);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let options = match parse_args(env::args().skip(1))? {
+        Cli::Run(options) => options,
+        Cli::Scents(action) => {
+            print!("{}", run_scents_action(&action)?);
+            return Ok(());
+        }
+        Cli::Plan(options) => return run_plan(&options),
+        Cli::Optimize(options) => return run_optimize(&options),
+        Cli::Analyze(options) => return run_analyze(&options),
+        Cli::Cover(options) => return run_cover(&options),
+        Cli::Solve(options) => return run_solve(&options),
+        Cli::Lint(options) => return run_lint(&options),
+        Cli::Heatmap(options) => return run_heatmap(&options),
+        Cli::Replay(options) => return run_replay(&options),
+        Cli::Debug(options) => return run_debug(&options),
+    };
+
+    let input = read_input()?;
+    let command = parse_input_to_command(&input)?;
 
-    let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+    let report = match options.scent_store {
+        ScentStoreKind::Memory => {
+            let scent_tracker: HashSet<RobotPosition> = HashSet::new();
+            run_with_store(&input, &command, &options, scent_tracker)?
+        }
+        ScentStoreKind::Bitset => {
+            let scent_tracker = BitsetScentStore::new(&command.bounds());
+            run_with_store(&input, &command, &options, scent_tracker)?
+        }
+    };
 
-    for robot in command.robots {
-        let robot = robot.process_all_commands(&command.upper_right, &mut scent_tracker);
-        robot.end_of_mission_report()
+    let stats = options.stats.then(|| MissionStats::new(&report));
+    match options.output {
+        OutputFormat::Text => {
+            print!("{}", report.text());
+            for collision in &report.collisions {
+                eprintln!("warning: {}", report.collision_message(collision))
+            }
+            if let Some(stats) = stats {
+                print!("{}", stats.text());
+            }
+        }
+        OutputFormat::Json => {
+            let mut json = report.to_json();
+            if let Some(stats) = stats {
+                json.push("stats", stats.to_json());
+            }
+            println!("{}", json)
+        }
+        OutputFormat::Ascii => print!("{}", render::ascii(&report)),
+        OutputFormat::Svg => print!("{}", render::svg(&report)),
     }
 
     Ok(())
@@ -1,6 +1,10 @@
+use crate::energy::EnergyCosts;
+use crate::models::RobotModel;
+use crate::plateau::{BoundaryPolicy, Obstacle, ObstaclePolicy, Plateau, Region};
 use crate::robots::{Robot, RobotPosition, RobotStatus};
+use crate::scents::{ScentDecay, ScentPolicy, ScentStore};
+use crate::simulation::CollisionPolicy;
 use anyhow::anyhow;
-use std::collections::HashSet;
 use std::fmt;
 use std::str::FromStr;
 
@@ -11,9 +15,9 @@ pub struct Coordinates {
 }
 
 impl Coordinates {
-    pub fn fallen_off_grid(&self, coordinate_limit: &Coordinates) -> bool {
-        if (self.x < 0 || self.x > coordinate_limit.x)
-            || (self.y < 0 || self.y > coordinate_limit.y)
+    pub fn fallen_off_grid(&self, bounds: &GridBounds) -> bool {
+        if (self.x < bounds.lower_left.x || self.x > bounds.upper_right.x)
+            || (self.y < bounds.lower_left.y || self.y > bounds.upper_right.y)
         {
             return true;
         }
@@ -39,7 +43,10 @@ impl FromStr for Coordinates {
         let mut coords = s.split(' ');
 
         let x_from_char = coords.next().unwrap().to_string().parse::<i32>()?;
-        let y_from_char = coords.next().unwrap().to_string().parse::<i32>()?;
+        let y_from_char = coords
+            .next()
+            .ok_or_else(|| anyhow!("Coordinates need an x and a y: {}", s))?
+            .parse::<i32>()?;
 
         Ok(Coordinates {
             x: x_from_char,
@@ -48,18 +55,171 @@ impl FromStr for Coordinates {
     }
 }
 
+/// The rectangle a robot is allowed to drive in, inclusive on both corners.
+/// Survey sectors use global coordinates so the lower left isn't always 0 0.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct GridBounds {
+    pub lower_left: Coordinates,
+    pub upper_right: Coordinates,
+}
+
+impl GridBounds {
+    pub fn width(&self) -> i32 {
+        self.upper_right.x - self.lower_left.x + 1
+    }
+
+    pub fn height(&self) -> i32 {
+        self.upper_right.y - self.lower_left.y + 1
+    }
+
+    /// Brings a square that has gone over an edge back in from the opposite
+    /// edge, as if the grid were wrapped around a torus.
+    pub fn wrap(&self, coordinates: &Coordinates) -> Coordinates {
+        Coordinates {
+            x: self.lower_left.x + (coordinates.x - self.lower_left.x).rem_euclid(self.width()),
+            y: self.lower_left.y + (coordinates.y - self.lower_left.y).rem_euclid(self.height()),
+        }
+    }
+
+    /// The 50 square limit applies to the size of the grid rather than to
+    /// where it sits, so check the distance between the two corners.
+    pub fn check_max_value(&self) -> Result<(), anyhow::Error> {
+        let span = Coordinates {
+            x: self.upper_right.x - self.lower_left.x,
+            y: self.upper_right.y - self.lower_left.y,
+        };
+        span.check_max_value()
+    }
+}
+
+impl From<Coordinates> for GridBounds {
+    fn from(upper_right: Coordinates) -> Self {
+        GridBounds {
+            lower_left: Coordinates { x: 0, y: 0 },
+            upper_right,
+        }
+    }
+}
+
+impl FromStr for GridBounds {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split_whitespace()
+            .map(|value| value.parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        match values[..] {
+            [x, y] => Ok(Coordinates { x, y }.into()),
+            [x1, y1, x2, y2] => Ok(GridBounds {
+                lower_left: Coordinates { x: x1, y: y1 },
+                upper_right: Coordinates { x: x2, y: y2 },
+            }),
+            _ => Err(anyhow!(
+                "Grid needs either an upper right point or both corners: {}",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GridBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.lower_left.x, self.lower_left.y, self.upper_right.x, self.upper_right.y
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Command {
+    pub lower_left: Coordinates,
     pub upper_right: Coordinates,
+    pub compass: Compass,
+    pub obstacles: Vec<Obstacle>,
+    pub obstacle_policy: ObstaclePolicy,
+    pub boundary: BoundaryPolicy,
+    pub region: Option<Region>,
+    pub scent_policy: ScentPolicy,
+    pub scent_decay: Option<ScentDecay>,
+    pub collision_policy: CollisionPolicy,
+    /// Every robot starts with this much energy when set.
+    pub energy: Option<u32>,
+    pub energy_costs: EnergyCosts,
+    pub models: Vec<RobotModel>,
     pub robots: Vec<Robot>,
 }
 
+impl Command {
+    pub fn bounds(&self) -> GridBounds {
+        GridBounds {
+            lower_left: self.lower_left.clone(),
+            upper_right: self.upper_right.clone(),
+        }
+    }
+
+    pub fn plateau(&self) -> Plateau {
+        Plateau::new(self.bounds())
+            .with_obstacles(self.obstacles.clone(), self.obstacle_policy)
+            .with_boundary(self.boundary)
+            .with_region(self.region.clone())
+            .with_scent_policy(self.scent_policy)
+    }
+}
+
+/// How many headings a robot can face. Four-way is the classic N/S/E/W grid,
+/// eight-way adds the diagonals and the half-turn instructions.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Compass {
+    #[default]
+    FourWay,
+    EightWay,
+}
+
+impl FromStr for Compass {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "4" => Ok(Compass::FourWay),
+            "8" => Ok(Compass::EightWay),
+            _ => Err(anyhow!("Compass must be either 4 or 8, got {}", input)),
+        }
+    }
+}
+
+impl fmt::Display for Compass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compass::FourWay => write!(f, "4"),
+            Compass::EightWay => write!(f, "8"),
+        }
+    }
+}
+
+impl Compass {
+    pub fn supports_orientation(&self, orientation: &Orientation) -> bool {
+        *self == Compass::EightWay || !orientation.is_diagonal()
+    }
+
+    pub fn supports_command(&self, command: &RobotCommands) -> bool {
+        *self == Compass::EightWay || !command.is_half_turn()
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Orientation {
     North,
     South,
     West,
     East,
+    NorthEast,
+    SouthEast,
+    SouthWest,
+    NorthWest,
 }
 
 impl fmt::Display for Orientation {
@@ -69,6 +229,10 @@ impl fmt::Display for Orientation {
             Orientation::South => "S",
             Orientation::West => "W",
             Orientation::East => "E",
+            Orientation::NorthEast => "NE",
+            Orientation::SouthEast => "SE",
+            Orientation::SouthWest => "SW",
+            Orientation::NorthWest => "NW",
         };
         write!(f, "{:}", val)
     }
@@ -83,25 +247,65 @@ impl FromStr for Orientation {
             "S" => Ok(Orientation::South),
             "W" => Ok(Orientation::West),
             "E" => Ok(Orientation::East),
+            "NE" => Ok(Orientation::NorthEast),
+            "SE" => Ok(Orientation::SouthEast),
+            "SW" => Ok(Orientation::SouthWest),
+            "NW" => Ok(Orientation::NorthWest),
             _ => Err(anyhow!("Error matching orientation")),
         }
     }
 }
 impl Orientation {
     pub fn change_right(&self) -> Self {
+        self.change_half_right().change_half_right()
+    }
+    pub fn change_left(&self) -> Self {
+        self.change_half_left().change_half_left()
+    }
+    pub fn change_half_right(&self) -> Self {
         match self {
-            Orientation::North => Orientation::East,
-            Orientation::East => Orientation::South,
-            Orientation::South => Orientation::West,
-            Orientation::West => Orientation::North,
+            Orientation::North => Orientation::NorthEast,
+            Orientation::NorthEast => Orientation::East,
+            Orientation::East => Orientation::SouthEast,
+            Orientation::SouthEast => Orientation::South,
+            Orientation::South => Orientation::SouthWest,
+            Orientation::SouthWest => Orientation::West,
+            Orientation::West => Orientation::NorthWest,
+            Orientation::NorthWest => Orientation::North,
         }
     }
-    pub fn change_left(&self) -> Self {
+    pub fn change_half_left(&self) -> Self {
+        match self {
+            Orientation::North => Orientation::NorthWest,
+            Orientation::NorthWest => Orientation::West,
+            Orientation::West => Orientation::SouthWest,
+            Orientation::SouthWest => Orientation::South,
+            Orientation::South => Orientation::SouthEast,
+            Orientation::SouthEast => Orientation::East,
+            Orientation::East => Orientation::NorthEast,
+            Orientation::NorthEast => Orientation::North,
+        }
+    }
+    pub fn is_diagonal(&self) -> bool {
+        matches!(
+            self,
+            Orientation::NorthEast
+                | Orientation::SouthEast
+                | Orientation::SouthWest
+                | Orientation::NorthWest
+        )
+    }
+    /// The (x, y) step taken by a single forward move with this heading.
+    pub fn delta(&self) -> (i32, i32) {
         match self {
-            Orientation::North => Orientation::West,
-            Orientation::East => Orientation::North,
-            Orientation::South => Orientation::East,
-            Orientation::West => Orientation::South,
+            Orientation::North => (0, 1),
+            Orientation::NorthEast => (1, 1),
+            Orientation::East => (1, 0),
+            Orientation::SouthEast => (1, -1),
+            Orientation::South => (0, -1),
+            Orientation::SouthWest => (-1, -1),
+            Orientation::West => (-1, 0),
+            Orientation::NorthWest => (-1, 1),
         }
     }
 }
@@ -111,14 +315,20 @@ pub enum RobotCommands {
     Left,
     Right,
     Forward,
+    HalfLeft,
+    HalfRight,
 }
 
 impl RobotCommands {
-    pub fn process(
+    pub fn is_half_turn(&self) -> bool {
+        matches!(self, RobotCommands::HalfLeft | RobotCommands::HalfRight)
+    }
+
+    pub fn process<S: ScentStore>(
         &self,
         robot_position: RobotPosition,
-        coordinate_limit: &Coordinates,
-        scent_tracker: &mut HashSet<RobotPosition>,
+        plateau: &Plateau,
+        scent_tracker: &mut S,
     ) -> (RobotPosition, RobotStatus) {
         match self {
             RobotCommands::Right => {
@@ -135,16 +345,60 @@ impl RobotCommands {
                     RobotStatus::Alive,
                 )
             }
+            RobotCommands::HalfRight => {
+                let new_orientation = robot_position.orientation.change_half_right();
+                (
+                    robot_position.update_orientation(new_orientation),
+                    RobotStatus::Alive,
+                )
+            }
+            RobotCommands::HalfLeft => {
+                let new_orientation = robot_position.orientation.change_half_left();
+                (
+                    robot_position.update_orientation(new_orientation),
+                    RobotStatus::Alive,
+                )
+            }
             RobotCommands::Forward => {
-                let scent_check = robot_position.check_scent(scent_tracker);
-                if scent_check {
-                    return (robot_position, RobotStatus::Alive);
+                // scents only mean something when the edge is deadly
+                if plateau.boundary == BoundaryPolicy::Lost {
+                    let scent = plateau.scent_policy.protecting_scent(
+                        &robot_position,
+                        plateau,
+                        scent_tracker,
+                    );
+                    if let Some(scent) = scent {
+                        scent_tracker.saved(&scent);
+                        return (robot_position, RobotStatus::Alive);
+                    }
                 }
-                let new_position = robot_position.move_forward();
-                let fallen_off_grid = new_position.coordinates.fallen_off_grid(coordinate_limit);
+                let mut new_position = robot_position.move_forward();
+                let fallen_off_grid = plateau.off_plateau(&new_position.coordinates);
                 if fallen_off_grid {
-                    robot_position.add_scent(scent_tracker);
-                    return (robot_position, RobotStatus::Lost);
+                    match plateau.boundary {
+                        BoundaryPolicy::Lost => {
+                            plateau
+                                .scent_policy
+                                .record(&robot_position, plateau, scent_tracker);
+                            return (robot_position, RobotStatus::Lost);
+                        }
+                        BoundaryPolicy::Clamp => return (robot_position, RobotStatus::Alive),
+                        BoundaryPolicy::Wrap => {
+                            new_position.coordinates =
+                                plateau.bounds.wrap(&new_position.coordinates);
+                            // wrapping only works across the bounding box, a
+                            // hole in the outline is treated like a wall
+                            if plateau.off_plateau(&new_position.coordinates) {
+                                return (robot_position, RobotStatus::Alive);
+                            }
+                        }
+                    }
+                }
+                if plateau.is_blocked(&new_position.coordinates) {
+                    return match plateau.obstacle_policy {
+                        ObstaclePolicy::Stay => (robot_position, RobotStatus::Alive),
+                        ObstaclePolicy::Crash => (robot_position, RobotStatus::Crashed),
+                    };
                 }
                 (new_position, RobotStatus::Alive)
             }
@@ -160,18 +414,33 @@ impl FromStr for RobotCommands {
             "L" => Ok(RobotCommands::Left),
             "R" => Ok(RobotCommands::Right),
             "F" => Ok(RobotCommands::Forward),
+            "l" => Ok(RobotCommands::HalfLeft),
+            "r" => Ok(RobotCommands::HalfRight),
             _ => Err(anyhow!("Error matching possible Robot Commands")),
         }
     }
 }
 
+impl fmt::Display for RobotCommands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RobotCommands::Left => write!(f, "L"),
+            RobotCommands::Right => write!(f, "R"),
+            RobotCommands::Forward => write!(f, "F"),
+            RobotCommands::HalfLeft => write!(f, "l"),
+            RobotCommands::HalfRight => write!(f, "r"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::mission_instructions::{Coordinates, Orientation, RobotCommands};
+    use crate::mission_instructions::{Coordinates, GridBounds, Orientation, RobotCommands};
     use std::collections::HashSet;
     use std::str::FromStr;
 
-    use crate::robots::Robot;
+    use crate::plateau::{BoundaryPolicy, Obstacle, ObstaclePolicy, Plateau, Region};
+    use crate::robots::{Robot, RobotStatus};
     use crate::RobotPosition;
     use rstest::*;
 
@@ -194,6 +463,46 @@ mod test {
         )
     }
 
+    #[rstest]
+    #[case("5 3", 0, 0, 5, 3)]
+    #[case("-20 -10 30 15", -20, -10, 30, 15)]
+    fn test_grid_bounds_from_str(
+        #[case] input: &str,
+        #[case] x1: i32,
+        #[case] y1: i32,
+        #[case] x2: i32,
+        #[case] y2: i32,
+    ) {
+        let bounds = GridBounds::from_str(input).unwrap();
+        assert_eq!(
+            bounds,
+            GridBounds {
+                lower_left: Coordinates { x: x1, y: y1 },
+                upper_right: Coordinates { x: x2, y: y2 },
+            }
+        )
+    }
+
+    #[rstest]
+    #[case("-20 -10 30 15", true)]
+    #[case("-20 -10 31 15", false)]
+    #[case("10 10 5 5", false)]
+    #[case("-1 3", false)]
+    fn test_grid_bounds_max_value(#[case] input: &str, #[case] valid: bool) {
+        let bounds = GridBounds::from_str(input).unwrap();
+        assert_eq!(bounds.check_max_value().is_ok(), valid)
+    }
+
+    #[rstest]
+    #[case(-20, -10, false)]
+    #[case(30, 15, false)]
+    #[case(-21, 0, true)]
+    #[case(0, 16, true)]
+    fn test_fallen_off_offset_grid(#[case] x: i32, #[case] y: i32, #[case] fallen: bool) {
+        let bounds = GridBounds::from_str("-20 -10 30 15").unwrap();
+        assert_eq!(Coordinates { x, y }.fallen_off_grid(&bounds), fallen)
+    }
+
     #[rstest]
     #[case("N", Orientation::North)]
     #[case("W", Orientation::West)]
@@ -239,12 +548,59 @@ mod test {
         assert_eq!(p, expected_orientation)
     }
 
+    #[rstest]
+    #[case(Orientation::North, Orientation::NorthEast, Orientation::NorthWest)]
+    #[case(Orientation::NorthEast, Orientation::East, Orientation::North)]
+    #[case(Orientation::SouthEast, Orientation::South, Orientation::East)]
+    #[case(Orientation::SouthWest, Orientation::West, Orientation::South)]
+    #[case(Orientation::NorthWest, Orientation::North, Orientation::West)]
+    fn test_half_turns(
+        #[case] input: Orientation,
+        #[case] expected_right: Orientation,
+        #[case] expected_left: Orientation,
+    ) {
+        assert_eq!(input.change_half_right(), expected_right);
+        assert_eq!(input.change_half_left(), expected_left);
+    }
+
+    #[rstest]
+    #[case(Orientation::NorthEast, 5, 3)]
+    #[case(Orientation::NorthEast, 5, 2)]
+    #[case(Orientation::NorthEast, 4, 3)]
+    #[case(Orientation::SouthWest, 0, 0)]
+    fn test_diagonal_corner_exit_leaves_scent(
+        #[case] orientation: Orientation,
+        #[case] x: i32,
+        #[case] y: i32,
+    ) {
+        let coordinate = Plateau::new(Coordinates { x: 5, y: 3 }.into());
+        let position = RobotPosition {
+            coordinates: Coordinates { x, y },
+            orientation,
+        };
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+
+        let (lost_at, status) =
+            RobotCommands::Forward.process(position.clone(), &coordinate, &mut scent_tracker);
+        assert_eq!(status, RobotStatus::Lost);
+        assert_eq!(lost_at, position);
+
+        let (saved_at, status) =
+            RobotCommands::Forward.process(position.clone(), &coordinate, &mut scent_tracker);
+        assert_eq!(status, RobotStatus::Alive);
+        assert_eq!(saved_at, position);
+
+        // the scent only covers the heading that was lost, not the whole corner
+        let turned = position.update_orientation(Orientation::North);
+        assert!(!turned.check_scent(&scent_tracker));
+    }
+
     #[rstest]
     #[case(RobotCommands::Forward)]
     fn test_processing_rob_commands_dont_move(#[case] input: RobotCommands) {
         let robot = Robot::new_basic_robot();
 
-        let coordinate = Coordinates { x: 5, y: 3 };
+        let coordinate = Plateau::new(Coordinates { x: 5, y: 3 }.into());
 
         let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
 
@@ -259,7 +615,7 @@ mod test {
     fn test_processing_rob_commands_fall_off_result(#[case] input: RobotCommands) {
         let robot = Robot::new_basic_robot();
 
-        let coordinate = Coordinates { x: 1, y: 1 };
+        let coordinate = Plateau::new(Coordinates { x: 1, y: 1 }.into());
 
         let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
 
@@ -272,11 +628,123 @@ mod test {
     fn test_processing_rob_commands_fall_off_scent(#[case] input: RobotCommands) {
         let robot = Robot::new_basic_robot();
 
-        let coordinate = Coordinates { x: 1, y: 1 };
+        let coordinate = Plateau::new(Coordinates { x: 1, y: 1 }.into());
 
         let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
 
-        let result = input.process(robot.position, &coordinate, &mut scent_tracker);
+        input.process(robot.position, &coordinate, &mut scent_tracker);
         insta::assert_debug_snapshot!(scent_tracker)
     }
+
+    #[rstest]
+    #[case(ObstaclePolicy::Stay, RobotStatus::Alive)]
+    #[case(ObstaclePolicy::Crash, RobotStatus::Crashed)]
+    fn test_forward_into_obstacle(
+        #[case] obstacle_policy: ObstaclePolicy,
+        #[case] expected_status: RobotStatus,
+    ) {
+        let robot = Robot::new_basic_robot();
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into())
+            .with_obstacles(vec![Obstacle::from_str("2 1").unwrap()], obstacle_policy);
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+
+        let (position, status) =
+            RobotCommands::Forward.process(robot.position.clone(), &plateau, &mut scent_tracker);
+
+        assert_eq!(position, robot.position);
+        assert_eq!(status, expected_status);
+        assert!(scent_tracker.is_empty());
+    }
+
+    #[rstest]
+    #[case(BoundaryPolicy::Lost, 5, 3, Orientation::East, RobotStatus::Lost)]
+    #[case(BoundaryPolicy::Wrap, 0, 3, Orientation::East, RobotStatus::Alive)]
+    #[case(BoundaryPolicy::Clamp, 5, 3, Orientation::East, RobotStatus::Alive)]
+    #[case(BoundaryPolicy::Wrap, 0, 0, Orientation::NorthEast, RobotStatus::Alive)]
+    fn test_forward_over_edge_by_boundary(
+        #[case] boundary: BoundaryPolicy,
+        #[case] expected_x: i32,
+        #[case] expected_y: i32,
+        #[case] orientation: Orientation,
+        #[case] expected_status: RobotStatus,
+    ) {
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into()).with_boundary(boundary);
+        let position = RobotPosition {
+            coordinates: Coordinates { x: 5, y: 3 },
+            orientation: orientation.clone(),
+        };
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+
+        let (new_position, status) =
+            RobotCommands::Forward.process(position, &plateau, &mut scent_tracker);
+
+        assert_eq!(
+            new_position,
+            RobotPosition {
+                coordinates: Coordinates {
+                    x: expected_x,
+                    y: expected_y
+                },
+                orientation,
+            }
+        );
+        assert_eq!(status, expected_status);
+        assert_eq!(
+            scent_tracker.len(),
+            (boundary == BoundaryPolicy::Lost) as usize
+        );
+    }
+
+    #[test]
+    fn test_scents_ignored_unless_lost_boundary() {
+        let plateau =
+            Plateau::new(Coordinates { x: 5, y: 3 }.into()).with_boundary(BoundaryPolicy::Wrap);
+        let position = RobotPosition {
+            coordinates: Coordinates { x: 5, y: 1 },
+            orientation: Orientation::East,
+        };
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+        position.add_scent(&mut scent_tracker);
+
+        let (new_position, _) =
+            RobotCommands::Forward.process(position, &plateau, &mut scent_tracker);
+
+        assert_eq!(new_position.coordinates, Coordinates { x: 0, y: 1 });
+    }
+
+    #[test]
+    fn test_wrap_offset_grid() {
+        let bounds = GridBounds::from_str("-20 -10 30 15").unwrap();
+        assert_eq!(
+            bounds.wrap(&Coordinates { x: 31, y: -11 }),
+            Coordinates { x: -20, y: 15 }
+        );
+    }
+
+    #[rstest]
+    #[case(4, 0, Orientation::East)]
+    #[case(1, 1, Orientation::NorthWest)]
+    #[case(3, 3, Orientation::North)]
+    fn test_leaving_region_is_lost_with_scent(
+        #[case] x: i32,
+        #[case] y: i32,
+        #[case] orientation: Orientation,
+    ) {
+        let plateau = Plateau::new(Coordinates { x: 4, y: 4 }.into())
+            .with_region(Some(Region::polygon_from_str("0 0 4 0 4 4").unwrap()));
+        let position = RobotPosition {
+            coordinates: Coordinates { x, y },
+            orientation,
+        };
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+
+        let (_, status) =
+            RobotCommands::Forward.process(position.clone(), &plateau, &mut scent_tracker);
+        assert_eq!(status, RobotStatus::Lost);
+
+        let (saved_at, status) =
+            RobotCommands::Forward.process(position.clone(), &plateau, &mut scent_tracker);
+        assert_eq!(status, RobotStatus::Alive);
+        assert_eq!(saved_at, position);
+    }
 }
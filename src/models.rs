@@ -0,0 +1,149 @@
+use crate::energy::EnergyCosts;
+use crate::mission_instructions::RobotCommands;
+use anyhow::anyhow;
+use std::str::FromStr;
+
+/// What a kind of robot can do, declared in the header with
+/// `model <name> key=value ...` and picked per robot with `model=<name>`.
+/// Anything a model leaves out is the same as for every other robot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobotModel {
+    pub name: String,
+    /// The commands the model understands, all of them when not given.
+    pub commands: Option<Vec<RobotCommands>>,
+    /// Models without a scent sensor drive straight past scents, although
+    /// they still leave one when they are lost.
+    pub scent_aware: bool,
+    /// Whether the model can drive forward while facing a diagonal heading.
+    pub diagonal: bool,
+    pub energy: Option<u32>,
+    pub forward_cost: Option<u32>,
+    pub turn_cost: Option<u32>,
+    pub blocked_cost: Option<u32>,
+}
+
+impl Default for RobotModel {
+    fn default() -> Self {
+        RobotModel {
+            name: "standard".to_string(),
+            commands: None,
+            scent_aware: true,
+            diagonal: true,
+            energy: None,
+            forward_cost: None,
+            turn_cost: None,
+            blocked_cost: None,
+        }
+    }
+}
+
+impl RobotModel {
+    pub fn allows(&self, command: &RobotCommands) -> bool {
+        self.commands
+            .as_ref()
+            .is_none_or(|commands| commands.contains(command))
+    }
+
+    /// The mission's costs with the model's own costs in their place.
+    pub fn costs(&self, mission: EnergyCosts) -> EnergyCosts {
+        EnergyCosts {
+            forward: self.forward_cost.unwrap_or(mission.forward),
+            turn: self.turn_cost.unwrap_or(mission.turn),
+            blocked: self.blocked_cost.unwrap_or(mission.blocked),
+        }
+    }
+}
+
+fn parse_flag(key: &str, value: &str, yes: &str, no: &str) -> Result<bool, anyhow::Error> {
+    match value {
+        _ if value == yes => Ok(true),
+        _ if value == no => Ok(false),
+        _ => Err(anyhow!("{} must be {} or {}, got {}", key, yes, no, value)),
+    }
+}
+
+impl FromStr for RobotModel {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut words = input.split_whitespace();
+        let name = words
+            .next()
+            .ok_or_else(|| anyhow!("A model needs a name, e.g. model mk2 scents=ignore"))?;
+        let mut model = RobotModel {
+            name: name.to_string(),
+            ..RobotModel::default()
+        };
+        for word in words {
+            let (key, value) = word.split_once('=').ok_or_else(|| {
+                anyhow!("Model {} settings must be key=value, got {}", name, word)
+            })?;
+            match key {
+                "commands" => {
+                    model.commands = Some(
+                        value
+                            .chars()
+                            .map(|c| RobotCommands::from_str(&c.to_string()))
+                            .collect::<Result<Vec<RobotCommands>, anyhow::Error>>()?,
+                    )
+                }
+                "scents" => model.scent_aware = parse_flag(key, value, "aware", "ignore")?,
+                "diagonal" => model.diagonal = parse_flag(key, value, "yes", "no")?,
+                "energy" => model.energy = Some(u32::from_str(value)?),
+                "forward" => model.forward_cost = Some(u32::from_str(value)?),
+                "turn" => model.turn_cost = Some(u32::from_str(value)?),
+                "blocked" => model.blocked_cost = Some(u32::from_str(value)?),
+                _ => return Err(anyhow!("Unknown setting {} for model {}", key, name)),
+            }
+        }
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::energy::EnergyCosts;
+    use crate::mission_instructions::RobotCommands;
+    use crate::models::RobotModel;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_model_from_str() {
+        let model =
+            RobotModel::from_str("mk2 commands=FR scents=ignore diagonal=no energy=30 forward=2")
+                .unwrap();
+
+        assert_eq!(
+            model,
+            RobotModel {
+                name: "mk2".to_string(),
+                commands: Some(vec![RobotCommands::Forward, RobotCommands::Right]),
+                scent_aware: false,
+                diagonal: false,
+                energy: Some(30),
+                forward_cost: Some(2),
+                turn_cost: None,
+                blocked_cost: None,
+            }
+        );
+        assert!(model.allows(&RobotCommands::Right));
+        assert!(!model.allows(&RobotCommands::Left));
+        assert_eq!(
+            model.costs(EnergyCosts::default()),
+            EnergyCosts {
+                forward: 2,
+                turn: 1,
+                blocked: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_bad_models() {
+        assert!(RobotModel::from_str("").is_err());
+        assert!(RobotModel::from_str("mk2 commands=FX").is_err());
+        assert!(RobotModel::from_str("mk2 scents=maybe").is_err());
+        assert!(RobotModel::from_str("mk2 wheels=6").is_err());
+        assert!(RobotModel::from_str("mk2 fast").is_err());
+    }
+}
@@ -0,0 +1,356 @@
+use crate::mission_instructions::{Command, Compass, Coordinates, RobotCommands};
+use crate::models::RobotModel;
+use crate::parser::writable_commands;
+use crate::robots::{RobotPosition, RobotStatus};
+use crate::scents::ScentLog;
+use crate::simulation::{run_mission, Schedule};
+use std::collections::{HashSet, VecDeque};
+
+/// How much shorter a robot's commands got.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Saving {
+    pub robot: usize,
+    pub label: String,
+    /// The input line the commands are on, counted from 1.
+    pub line: usize,
+    pub before: Vec<RobotCommands>,
+    pub after: Vec<RobotCommands>,
+}
+
+impl Saving {
+    pub fn saved(&self) -> usize {
+        self.before.len() - self.after.len()
+    }
+}
+
+/// Eighths of a full turn clockwise.
+fn eighths(command: &RobotCommands) -> Option<u8> {
+    match command {
+        RobotCommands::Right => Some(2),
+        RobotCommands::HalfRight => Some(1),
+        RobotCommands::Left => Some(6),
+        RobotCommands::HalfLeft => Some(7),
+        RobotCommands::Forward => None,
+    }
+}
+
+/// The turns a robot can make on the compass, leaving out any its model
+/// can't carry out.
+fn available_turns(compass: &Compass, model: Option<&RobotModel>) -> Vec<RobotCommands> {
+    use RobotCommands::*;
+    [Right, HalfRight, Left, HalfLeft]
+        .into_iter()
+        .filter(|turn| compass.supports_command(turn))
+        .filter(|turn| model.is_none_or(|model| model.allows(turn)))
+        .collect()
+}
+
+/// The fewest of `turns` that face the same way as turning `target` eighths
+/// clockwise, if they can at all.
+fn shortest_turn(target: u8, turns: &[RobotCommands]) -> Option<Vec<RobotCommands>> {
+    // breadth first, so the first way found to face each heading is one of
+    // the shortest
+    let mut routes: [Option<Vec<RobotCommands>>; 8] = Default::default();
+    routes[0] = Some(vec![]);
+    let mut queue = VecDeque::from([0]);
+    while let Some(heading) = queue.pop_front() {
+        for turn in turns {
+            let next = (heading + eighths(turn)?) as usize % 8;
+            if routes[next].is_none() {
+                let mut route = routes[heading as usize].clone()?;
+                route.push(turn.clone());
+                routes[next] = Some(route);
+                queue.push_back(next as u8);
+            }
+        }
+    }
+    routes[target as usize].take()
+}
+
+/// A run of turns that could be done with fewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WastedTurns {
+    /// Where the run starts in the commands, counted from 0.
+    pub at: usize,
+    pub turns: Vec<RobotCommands>,
+    /// The fewest turns the robot can make that end up facing the same way.
+    pub shortest: Vec<RobotCommands>,
+}
+
+/// Every run of turns in the commands that the robot could do with fewer,
+/// given the compass and what its model can carry out.
+pub fn wasted_turns(
+    commands: &[RobotCommands],
+    compass: &Compass,
+    model: Option<&RobotModel>,
+) -> Vec<WastedTurns> {
+    let available = available_turns(compass, model);
+    let mut wasted = vec![];
+    let mut at = 0;
+    while at < commands.len() {
+        let run = commands[at..]
+            .iter()
+            .take_while(|command| eighths(command).is_some())
+            .count();
+        if run == 0 {
+            at += 1;
+            continue;
+        }
+        let turns = commands[at..at + run].to_vec();
+        let total = turns
+            .iter()
+            .filter_map(eighths)
+            .fold(0, |total, turn| (total + turn) % 8);
+        let shortest = shortest_turn(total, &available);
+        if let Some(shortest) = shortest.filter(|shortest| shortest.len() < turns.len()) {
+            wasted.push(WastedTurns {
+                at,
+                turns,
+                shortest,
+            });
+        }
+        at += run;
+    }
+    wasted
+}
+
+/// Replaces every run of turns with the fewest turns the robot can make that
+/// end up facing the same way, so `RRRR` and `LR` go and `RRR` becomes `L`,
+/// or stays `RRR` for a model that can only turn right.
+pub fn collapse_turns(
+    commands: &[RobotCommands],
+    compass: &Compass,
+    model: Option<&RobotModel>,
+) -> Vec<RobotCommands> {
+    let mut collapsed = vec![];
+    let mut next = 0;
+    for wasted in wasted_turns(commands, compass, model) {
+        collapsed.extend_from_slice(&commands[next..wasted.at]);
+        collapsed.extend(wasted.shortest);
+        next = wasted.at + wasted.turns.len();
+    }
+    collapsed.extend_from_slice(&commands[next..]);
+    collapsed
+}
+
+/// What a mission did that the optimizer mustn't change: where every robot
+/// ended up, which scents got laid and by whom, who each scent saved and every
+/// collision. When in the mission things happened, and energy left over, may
+/// change.
+#[derive(Debug, PartialEq)]
+struct Footprint {
+    robots: Vec<(RobotPosition, RobotStatus)>,
+    scents: Vec<(RobotPosition, usize)>,
+    expired_scents: Vec<(RobotPosition, usize)>,
+    saved_by: Vec<(usize, RobotPosition, usize)>,
+    collisions: Vec<(usize, usize, Coordinates)>,
+}
+
+fn footprint(command: &Command) -> Footprint {
+    let mut scent_log = ScentLog::new(HashSet::<RobotPosition>::new(), command.scent_decay);
+    let outcome = run_mission(command, Schedule::Sequential, &mut scent_log);
+    let scent_footprint = |scents: Vec<crate::scents::Scent>| {
+        scents
+            .into_iter()
+            .map(|scent| (scent.position, scent.laid.robot))
+            .collect()
+    };
+
+    Footprint {
+        robots: outcome
+            .robots
+            .into_iter()
+            .map(|robot| (robot.position, robot.robot_status))
+            .collect(),
+        scents: scent_footprint(scent_log.active()),
+        expired_scents: scent_footprint(scent_log.expired()),
+        saved_by: scent_log
+            .saved_by()
+            .iter()
+            .map(|saved_by| {
+                (
+                    saved_by.saved.robot,
+                    saved_by.scent.position.clone(),
+                    saved_by.scent.laid.robot,
+                )
+            })
+            .collect(),
+        collisions: outcome
+            .collisions
+            .into_iter()
+            .map(|collision| (collision.robot, collision.other, collision.at))
+            .collect(),
+    }
+}
+
+/// Shortens each robot's commands in turn, keeping a shorter sequence only
+/// when the whole mission, run sequentially, leaves the same footprint as it
+/// did before, and can still be written out. Returns the optimised mission
+/// and what was saved.
+pub fn optimize(command: &Command) -> (Command, Vec<Saving>) {
+    let mut optimized = command.clone();
+    let mut savings = vec![];
+    let expected = footprint(command);
+
+    for index in 0..optimized.robots.len() {
+        let robot = &optimized.robots[index];
+        let shorter = collapse_turns(
+            &robot.robot_commands,
+            &command.compass,
+            robot.model.as_ref(),
+        );
+        if !writable_commands(&shorter) || shorter.len() == robot.robot_commands.len() {
+            continue;
+        }
+
+        let mut candidate = optimized.clone();
+        candidate.robots[index] = robot.clone().update_commands(shorter.clone());
+        if footprint(&candidate) == expected {
+            let robot = &optimized.robots[index];
+            savings.push(Saving {
+                robot: index,
+                label: robot.label(index),
+                line: robot.line,
+                before: robot.robot_commands.clone(),
+                after: shorter,
+            });
+            optimized = candidate;
+        }
+    }
+
+    (optimized, savings)
+}
+
+/// The mission text with each optimised robot's commands line rewritten,
+/// everything else kept as it was.
+pub fn rewrite(input: &str, savings: &[Saving]) -> String {
+    let mut lines: Vec<String> = input.split('\n').map(String::from).collect();
+    for saving in savings {
+        if let Some(line) = lines.get_mut(saving.line - 1) {
+            let ending = if line.ends_with('\r') { "\r" } else { "" };
+            *line = format!(
+                "{}{}",
+                saving
+                    .after
+                    .iter()
+                    .map(|command| command.to_string())
+                    .collect::<String>(),
+                ending
+            );
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mission_instructions::{Compass, RobotCommands};
+    use crate::optimizer::{collapse_turns, optimize, rewrite, wasted_turns};
+    use crate::parser::parse_input_to_command;
+    use rstest::rstest;
+    use std::str::FromStr;
+
+    fn commands(input: &str) -> Vec<RobotCommands> {
+        input
+            .chars()
+            .map(|c| RobotCommands::from_str(&c.to_string()).unwrap())
+            .collect()
+    }
+
+    #[rstest]
+    #[case("RRRR", "")]
+    #[case("LR", "")]
+    #[case("RRR", "L")]
+    #[case("FRRRFLLLF", "FLFRF")]
+    #[case("RR", "RR")]
+    #[case("LL", "LL")]
+    #[case("rrr", "Rr")]
+    #[case("lR", "r")]
+    #[case("RRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRR", "R")]
+    #[case("FRF", "FRF")]
+    fn test_collapse_turns(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(
+            collapse_turns(&commands(input), &Compass::EightWay, None),
+            commands(expected)
+        );
+    }
+
+    #[test]
+    fn test_wasted_turns() {
+        let wasted = wasted_turns(&commands("FRRRFLRFRR"), &Compass::FourWay, None);
+
+        assert_eq!(wasted.len(), 2);
+        assert_eq!(wasted[0].at, 1);
+        assert_eq!(wasted[0].shortest, commands("L"));
+        assert_eq!(wasted[1].at, 5);
+        assert_eq!(wasted[1].turns, commands("LR"));
+        assert_eq!(wasted[1].shortest, vec![]);
+    }
+
+    #[test]
+    fn test_optimize_reports_savings() {
+        let command = parse_input_to_command("5 3\n1 1 E\nRRRRFLR\n\n3 2 N\nFRF").unwrap();
+
+        let (optimized, savings) = optimize(&command);
+
+        assert_eq!(savings.len(), 1);
+        assert_eq!(savings[0].robot, 0);
+        assert_eq!(savings[0].line, 3);
+        assert_eq!(savings[0].saved(), 6);
+        assert_eq!(optimized.robots[0].robot_commands, commands("F"));
+        assert_eq!(optimized.robots[1].robot_commands, commands("FRF"));
+    }
+
+    #[test]
+    fn test_optimize_keeps_commands_that_change_the_outcome() {
+        // the wasted turns let the first robot's scent decay before the second
+        // robot reaches the edge
+        let command =
+            parse_input_to_command("decay ticks 3\n5 3\n3 3 N\nF\n\n3 3 N\nRRRRF").unwrap();
+
+        let (optimized, savings) = optimize(&command);
+
+        assert_eq!(savings, vec![]);
+        assert_eq!(optimized, command);
+    }
+
+    #[test]
+    fn test_rewrite_only_touches_command_lines() {
+        let input = "compass 4\n5 3\n1 1 E\nRRRRFLR\n\n3 2 N\nRRR\r\n";
+        let command = parse_input_to_command(input).unwrap();
+
+        let (_, savings) = optimize(&command);
+
+        assert_eq!(
+            rewrite(input, &savings),
+            "compass 4\n5 3\n1 1 E\nF\n\n3 2 N\nL\r\n"
+        );
+    }
+
+    #[test]
+    fn test_rewritten_mission_still_parses() {
+        let input = "5 3\n1 1 E\nRRRR\n\n3 2 N\nLRFRRR\n\n0 0 N\nLR\n";
+        let command = parse_input_to_command(input).unwrap();
+
+        let (_, savings) = optimize(&command);
+        let rewritten = parse_input_to_command(&rewrite(input, &savings)).unwrap();
+
+        assert_eq!(rewritten.robots.len(), 3);
+        assert_eq!(rewritten.robots[0].robot_commands, commands("RRRR"));
+        assert_eq!(rewritten.robots[1].robot_commands, commands("FL"));
+        assert_eq!(rewritten.robots[2].robot_commands, commands("LR"));
+    }
+
+    #[test]
+    fn test_optimize_respects_the_robot_model() {
+        let input = "model righty commands=FR\n5 3\n@a model=righty\n1 1 E\nRRRFRRRRRFRRRRRRRF\n";
+        let command = parse_input_to_command(input).unwrap();
+
+        let (optimized, savings) = optimize(&command);
+
+        // RRR can't become L, but RRRRR can still become R and RRRRRRR RRR
+        assert_eq!(optimized.robots[0].robot_commands, commands("RRRFRFRRRF"));
+        assert_eq!(savings[0].saved(), 8);
+        parse_input_to_command(&rewrite(input, &savings)).unwrap();
+    }
+}
@@ -1,76 +1,295 @@
-use crate::mission_instructions::{Command, Coordinates, Orientation, RobotCommands};
+use crate::energy::EnergyCosts;
+use crate::mission_instructions::{Command, Compass, GridBounds, RobotCommands};
+use crate::models::RobotModel;
+use crate::plateau::{BoundaryPolicy, Obstacle, ObstaclePolicy, Region};
 use crate::robots::{Robot, RobotPosition};
+use crate::scents::{ScentDecay, ScentPolicy};
+use crate::simulation::CollisionPolicy;
 use anyhow::anyhow;
-use itertools::Itertools;
 use std::error;
 use std::str::{FromStr, Lines};
 
+/// The most commands a single robot can be given.
+pub const MAX_ROBOT_COMMANDS: usize = 100;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct MissionHeader {
+    compass: Compass,
+    obstacles: Vec<Obstacle>,
+    obstacle_policy: ObstaclePolicy,
+    boundary: BoundaryPolicy,
+    polygon: Option<Region>,
+    mask_rows: Vec<String>,
+    scent_policy: ScentPolicy,
+    scent_decay: Option<ScentDecay>,
+    collision_policy: CollisionPolicy,
+    energy: Option<u32>,
+    energy_costs: EnergyCosts,
+    models: Vec<RobotModel>,
+}
+
 pub fn parse_input_to_command(commands: &str) -> Result<Command, Box<dyn error::Error>> {
     let mut lines_of_instruction = commands.lines();
+    let header = parse_header(&mut lines_of_instruction)?;
     let coords = lines_of_instruction.next();
     // match until cover the lines so that can unwrap.
-    let coordinates = match coords {
+    let bounds = match coords {
         None => Err(anyhow!("No Upper right point provided.")),
-        Some(values) => Ok(Coordinates::from_str(values)?),
+        Some(values) => Ok(GridBounds::from_str(values)?),
     }?;
 
-    coordinates.check_max_value()?;
+    bounds.check_max_value()?;
+
+    for obstacle in &header.obstacles {
+        if obstacle.lower_left.fallen_off_grid(&bounds)
+            || obstacle.upper_right.fallen_off_grid(&bounds)
+        {
+            return Err(anyhow!("Obstacle {:?} is outside of the grid", obstacle).into());
+        }
+    }
+
+    let region = match (header.polygon, header.mask_rows.is_empty()) {
+        (Some(_), false) => {
+            return Err(anyhow!("A plateau can have a polygon or a mask, not both").into())
+        }
+        (Some(polygon), true) => Some(polygon),
+        (None, false) => Some(Region::mask_from_rows(&header.mask_rows, &bounds)?),
+        (None, true) => None,
+    };
 
-    let robots = parse_robot_commands(lines_of_instruction)?;
+    let first_robot_line = commands.lines().count() - lines_of_instruction.clone().count() + 1;
+    let robots = parse_robot_commands(lines_of_instruction, first_robot_line, &header.compass)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, robot)| assign_model(robot, index, &header.models, header.energy))
+        .collect::<Result<Vec<Robot>, anyhow::Error>>()?;
 
     let command = Command {
-        upper_right: coordinates,
+        lower_left: bounds.lower_left,
+        upper_right: bounds.upper_right,
+        compass: header.compass,
+        obstacles: header.obstacles,
+        obstacle_policy: header.obstacle_policy,
+        boundary: header.boundary,
+        region,
+        scent_policy: header.scent_policy,
+        scent_decay: header.scent_decay,
+        collision_policy: header.collision_policy,
+        energy: header.energy,
+        energy_costs: header.energy_costs,
+        models: header.models,
         robots,
     };
 
+    let plateau = command.plateau();
+    for (index, robot) in command.robots.iter().enumerate() {
+        if plateau.is_blocked(&robot.position.coordinates) {
+            return Err(anyhow!(
+                "Robot {} dropped on an obstacle at {}",
+                robot.label(index),
+                robot.position
+            )
+            .into());
+        }
+        if command.region.is_some() && plateau.off_plateau(&robot.position.coordinates) {
+            return Err(anyhow!(
+                "Robot {} dropped outside the plateau at {}",
+                robot.label(index),
+                robot.position
+            )
+            .into());
+        }
+        if let Some(id) = &robot.id {
+            if command.robots[..index]
+                .iter()
+                .any(|other| other.id.as_ref() == Some(id))
+            {
+                return Err(anyhow!("Robot id {} is used more than once", id).into());
+            }
+        }
+    }
+
     Ok(command)
 }
 
-fn parse_robot_commands(lines: Lines) -> Result<Vec<Robot>, anyhow::Error> {
-    let trimmed_lines = remove_lines_and_whitespace(lines);
+/// The header and grid lines of a mission as written, without its robots.
+pub fn plateau_lines(commands: &str) -> Vec<&str> {
+    let mut lines = commands.lines();
+    let mut plateau: Vec<&str> = lines
+        .clone()
+        .take_while(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+        .collect();
+    plateau.extend(lines.nth(plateau.len()));
+    plateau
+}
+
+/// Whether `commands` can be written out as a robot's commands line. Blank
+/// lines are skipped, so a robot written with no commands would have the next
+/// robot's position read as its commands.
+pub fn writable_commands(commands: &[RobotCommands]) -> bool {
+    !commands.is_empty()
+}
 
-    // assume current structure will stay the same with 2 lines = one robot
-    let robot_infos = generate_robots_from_strs(trimmed_lines)?;
+/// Header directives are optional `keyword value` lines before the grid line,
+/// e.g. `compass 8` or `obstacle 1 1 2 2`. The grid line always starts with a
+/// number so the first line that doesn't start with a letter ends the header.
+fn parse_header(lines: &mut Lines) -> Result<MissionHeader, anyhow::Error> {
+    let mut header = MissionHeader::default();
+    while let Some(line) = lines.clone().next() {
+        if !line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            break;
+        }
+        lines.next();
+        let (directive, value) = line.split_once(' ').unwrap_or((line, ""));
+        match directive {
+            "compass" => header.compass = Compass::from_str(value.trim())?,
+            "obstacle" => header.obstacles.push(Obstacle::from_str(value)?),
+            "on-obstacle" => header.obstacle_policy = ObstaclePolicy::from_str(value.trim())?,
+            "boundary" => header.boundary = BoundaryPolicy::from_str(value.trim())?,
+            "polygon" => header.polygon = Some(Region::polygon_from_str(value)?),
+            "mask" => header.mask_rows.push(value.trim().to_string()),
+            "scent" => header.scent_policy = ScentPolicy::from_str(value.trim())?,
+            "decay" => header.scent_decay = Some(ScentDecay::from_str(value.trim())?),
+            "on-collision" => header.collision_policy = CollisionPolicy::from_str(value.trim())?,
+            "energy" => header.energy = Some(u32::from_str(value.trim())?),
+            "cost" => header.energy_costs.set(value)?,
+            "model" => {
+                let model = RobotModel::from_str(value)?;
+                if header.models.iter().any(|known| known.name == model.name) {
+                    return Err(anyhow!("Model {} is declared more than once", model.name));
+                }
+                header.models.push(model)
+            }
+            _ => return Err(anyhow!("Unknown mission header directive: {}", directive)),
+        }
+    }
+    Ok(header)
+}
+
+fn parse_robot_commands(
+    lines: Lines,
+    first_line: usize,
+    compass: &Compass,
+) -> Result<Vec<Robot>, anyhow::Error> {
+    let trimmed_lines = remove_lines_and_whitespace(lines)
+        .into_iter()
+        .map(|(index, line)| (first_line + index, line))
+        .collect();
+
+    let robot_infos = generate_robots_from_strs(trimmed_lines, compass)?;
 
     Ok(robot_infos)
 }
 
-fn remove_lines_and_whitespace(lines: Lines) -> Vec<String> {
-    let mut removed_lines: Vec<String> = vec![];
-    for line in lines {
+/// Drops the empty lines, keeping where each of the others was in `lines`.
+fn remove_lines_and_whitespace(lines: Lines) -> Vec<(usize, String)> {
+    let mut removed_lines: Vec<(usize, String)> = vec![];
+    for (index, line) in lines.enumerate() {
         if line.is_empty() {
             // do nothing because it's an empty line.
         } else {
-            removed_lines.push(line.to_string())
+            removed_lines.push((index, line.to_string()))
         }
     }
     removed_lines
 }
 
-fn generate_robots_from_strs(trimmed_strings: Vec<String>) -> Result<Vec<Robot>, anyhow::Error> {
+/// Gives the robot the model named in its `model=` metadata, checking the
+/// model can carry out all of its commands, and its starting energy.
+fn assign_model(
+    robot: Robot,
+    index: usize,
+    models: &[RobotModel],
+    energy: Option<u32>,
+) -> Result<Robot, anyhow::Error> {
+    let model = match robot.metadata_value("model") {
+        None => None,
+        Some(name) => Some(
+            models
+                .iter()
+                .find(|model| model.name == name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Robot {}: unknown model {}", robot.label(index), name))?,
+        ),
+    };
+    if let Some(model) = &model {
+        if let Some(command) = robot.robot_commands.iter().find(|c| !model.allows(c)) {
+            return Err(anyhow!(
+                "Robot {}: model {} can't carry out {}",
+                robot.label(index),
+                model.name,
+                command
+            ));
+        }
+    }
+    let energy = model.as_ref().and_then(|model| model.energy).or(energy);
+    Ok(robot.set_energy(energy).set_model(model))
+}
+
+/// Reads an `@id key=value ...` line naming the robot that follows it.
+fn parse_robot_tag(tag: &str) -> Result<(String, Vec<(String, String)>), anyhow::Error> {
+    let mut words = tag.split_whitespace();
+    let id = words
+        .next()
+        .ok_or_else(|| anyhow!("A robot tag needs an id after the @"))?;
+    let metadata = words
+        .map(|word| {
+            word.split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| anyhow!("Robot {} metadata must be key=value, got {}", id, word))
+        })
+        .collect::<Result<Vec<(String, String)>, anyhow::Error>>()?;
+    Ok((id.to_string(), metadata))
+}
+
+/// Each robot is its position line followed by its commands line, optionally
+/// named by an `@id` line in front. Lines are numbered from the input.
+fn generate_robots_from_strs(
+    trimmed_strings: Vec<(usize, String)>,
+    compass: &Compass,
+) -> Result<Vec<Robot>, anyhow::Error> {
     let mut robots = vec![];
-    for chunk in &trimmed_strings.into_iter().chunks(2) {
+    let mut lines = trimmed_strings.into_iter().peekable();
+    while let Some((line, robot_info)) = lines.next() {
         let mut robot = Robot::new();
-        for (index, robot_info) in chunk.into_iter().enumerate() {
-            if index == 0 {
-                let info = robot_info.rsplit_once(' ').unwrap();
-                let coordinates = Coordinates::from_str(info.0)?;
-                let orientation = Orientation::from_str(info.1)?;
-                let robot_position = RobotPosition {
-                    coordinates,
-                    orientation,
-                };
-                robot = robot.set_start_position(robot_position);
-            } else {
-                let robot_commands: Vec<RobotCommands> = robot_info
-                    .chars()
-                    .map(|test| RobotCommands::from_str(test.to_string().as_str()))
-                    .collect::<Result<Vec<RobotCommands>, anyhow::Error>>()?;
-                if robot_commands.len() > 100 {
-                    return Err(anyhow!("Too many robot commands provided"));
-                }
-                robot = robot.update_commands(robot_commands);
+        let (line, robot_info) = match robot_info.strip_prefix('@') {
+            Some(tag) => {
+                let (id, metadata) = parse_robot_tag(tag)?;
+                let position = lines
+                    .next_if(|(_, next)| !next.starts_with('@'))
+                    .ok_or_else(|| anyhow!("Robot {} has no position", id))?;
+                robot = robot.set_identity(id, metadata);
+                position
+            }
+            None => (line, robot_info),
+        };
+        let label = robot.label(robots.len());
+
+        let robot_position = RobotPosition::from_str(&robot_info)?;
+        if !compass.supports_orientation(&robot_position.orientation) {
+            return Err(anyhow!(
+                "Robot {}: orientation {} needs the 8-way compass (compass 8)",
+                label,
+                robot_position.orientation
+            ));
+        }
+        robot = robot.set_start_position(robot_position).set_line(line);
+
+        if let Some((line, robot_info)) = lines.next_if(|(_, next)| !next.starts_with('@')) {
+            let robot_commands: Vec<RobotCommands> = robot_info
+                .chars()
+                .map(|test| RobotCommands::from_str(test.to_string().as_str()))
+                .collect::<Result<Vec<RobotCommands>, anyhow::Error>>()?;
+            if robot_commands.iter().any(|c| !compass.supports_command(c)) {
+                return Err(anyhow!(
+                    "Robot {}: half-turn commands need the 8-way compass (compass 8)",
+                    label
+                ));
+            }
+            if robot_commands.len() > MAX_ROBOT_COMMANDS {
+                return Err(anyhow!("Robot {}: too many robot commands provided", label));
             }
+            robot = robot.update_commands(robot_commands).set_line(line);
         }
         robots.push(robot)
     }
@@ -80,10 +299,15 @@ fn generate_robots_from_strs(trimmed_strings: Vec<String>) -> Result<Vec<Robot>,
 
 #[cfg(test)]
 mod test {
+    use crate::mission_instructions::{Compass, Coordinates, Orientation, RobotCommands};
     use crate::parser::{
         generate_robots_from_strs, parse_input_to_command, parse_robot_commands,
         remove_lines_and_whitespace,
     };
+    use crate::plateau::{BoundaryPolicy, ObstaclePolicy, Region};
+    use crate::scents::{ScentDecay, ScentPolicy};
+    use crate::simulation::CollisionPolicy;
+    use rstest::*;
 
     #[test]
     fn test_parse_input_commands() {
@@ -117,7 +341,7 @@ mod test {
         let str = "3 2 N\nFRRFLLFFRRFLL";
         let lines = str.lines();
 
-        let robot_commands = parse_robot_commands(lines).unwrap();
+        let robot_commands = parse_robot_commands(lines, 1, &Compass::FourWay).unwrap();
 
         insta::assert_debug_snapshot!(robot_commands)
     }
@@ -127,7 +351,7 @@ mod test {
         let str = "1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL";
         let lines = str.lines();
 
-        let robot_commands = parse_robot_commands(lines).unwrap();
+        let robot_commands = parse_robot_commands(lines, 1, &Compass::FourWay).unwrap();
 
         insta::assert_debug_snapshot!(robot_commands)
     }
@@ -138,7 +362,7 @@ mod test {
         let lines = str.lines();
 
         let removed_lines = remove_lines_and_whitespace(lines);
-        let expected = vec!["3 2 N".to_string(), "FRRFLLFFRRFLL".to_string()];
+        let expected = vec![(0, "3 2 N".to_string()), (1, "FRRFLLFFRRFLL".to_string())];
 
         assert_eq!(removed_lines, expected)
     }
@@ -149,30 +373,234 @@ mod test {
         let lines = str.lines();
 
         let removed_lines = remove_lines_and_whitespace(lines);
-        let expected = vec!["3 2 N".to_string(), "FRRFLLFFRRFLL".to_string()];
+        let expected = vec![(0, "3 2 N".to_string()), (1, "FRRFLLFFRRFLL".to_string())];
 
         assert_eq!(removed_lines, expected)
     }
 
     #[test]
     fn test_generate_robots_from_strs() {
-        let robots =
-            generate_robots_from_strs(vec!["3 2 N".to_string(), "FRRFLLFFRRFLL".to_string()])
-                .unwrap();
+        let robots = generate_robots_from_strs(
+            vec![(1, "3 2 N".to_string()), (2, "FRRFLLFFRRFLL".to_string())],
+            &Compass::FourWay,
+        )
+        .unwrap();
 
         insta::assert_debug_snapshot!(robots)
     }
 
     #[test]
     fn test_generate_robots_from_strs_two_robots() {
-        let robots = generate_robots_from_strs(vec![
-            "1 1 E".to_string(),
-            "RFRFRFRF".to_string(),
-            "3 2 N".to_string(),
-            "FRRFLLFFRRFLL".to_string(),
-        ])
+        let robots = generate_robots_from_strs(
+            vec![
+                (1, "1 1 E".to_string()),
+                (2, "RFRFRFRF".to_string()),
+                (4, "3 2 N".to_string()),
+                (5, "FRRFLLFFRRFLL".to_string()),
+            ],
+            &Compass::FourWay,
+        )
         .unwrap();
 
         insta::assert_debug_snapshot!(robots)
     }
+
+    #[test]
+    fn test_robot_command_lines() {
+        let str = "compass 8\n5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n\n0 3 W";
+
+        let command = parse_input_to_command(str).unwrap();
+        let lines: Vec<usize> = command.robots.iter().map(|robot| robot.line).collect();
+
+        assert_eq!(lines, vec![4, 7, 10]);
+    }
+
+    #[test]
+    fn test_parse_eight_way_header() {
+        let str = "compass 8\n5 3\n1 1 NE\nFrFlF";
+
+        let command = parse_input_to_command(str).unwrap();
+
+        assert_eq!(command.compass, Compass::EightWay);
+        assert_eq!(
+            command.robots[0].position.orientation,
+            Orientation::NorthEast
+        );
+        assert_eq!(
+            command.robots[0].robot_commands,
+            vec![
+                RobotCommands::Forward,
+                RobotCommands::HalfRight,
+                RobotCommands::Forward,
+                RobotCommands::HalfLeft,
+                RobotCommands::Forward,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_four_way_rejects_diagonals() {
+        assert!(parse_input_to_command("5 3\n1 1 NE\nF").is_err());
+        assert!(parse_input_to_command("5 3\n1 1 N\nFrF").is_err());
+        assert!(parse_input_to_command("compass 6\n5 3\n1 1 N\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_obstacles_header() {
+        let str = "obstacle 2 2\nobstacle 3 0 4 1\non-obstacle crash\n5 3\n1 1 E\nFFF";
+
+        let command = parse_input_to_command(str).unwrap();
+
+        insta::assert_debug_snapshot!(command.obstacles);
+        assert_eq!(command.obstacle_policy, ObstaclePolicy::Crash);
+    }
+
+    #[test]
+    fn test_obstacle_errors() {
+        assert!(parse_input_to_command("obstacle 6 1\n5 3\n1 1 E\nF").is_err());
+        assert!(parse_input_to_command("obstacle 1 1\n5 3\n1 1 E\nF").is_err());
+        assert!(parse_input_to_command("on-obstacle bounce\n5 3\n1 1 E\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_offset_grid() {
+        let str = "-20 -10 30 15\n-20 -10 S\nF\n\n29 14 E\nF";
+
+        let command = parse_input_to_command(str).unwrap();
+
+        assert_eq!(command.lower_left, Coordinates { x: -20, y: -10 });
+        assert_eq!(command.upper_right, Coordinates { x: 30, y: 15 });
+        assert!(parse_input_to_command("-20 -10 31 15\n0 0 N\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_boundary_header() {
+        let command = parse_input_to_command("boundary wrap\n5 3\n1 1 E\nF").unwrap();
+        assert_eq!(command.boundary, BoundaryPolicy::Wrap);
+
+        let command = parse_input_to_command("5 3\n1 1 E\nF").unwrap();
+        assert_eq!(command.boundary, BoundaryPolicy::Lost);
+
+        assert!(parse_input_to_command("boundary bounce\n5 3\n1 1 E\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_region_header() {
+        let command = parse_input_to_command("polygon 0 0 5 0 5 3\n5 3\n4 1 N\nF").unwrap();
+        assert!(matches!(command.region, Some(Region::Polygon(_))));
+        assert_eq!(command.upper_right, Coordinates { x: 5, y: 3 });
+
+        let command = parse_input_to_command("mask ###\nmask .##\n2 1\n1 0 N\nF").unwrap();
+        assert_eq!(
+            command.region,
+            Some(Region::Mask(vec![
+                vec![true, true, true],
+                vec![false, true, true]
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_region_header_errors() {
+        assert!(parse_input_to_command("mask ###\n2 1\n1 0 N\nF").is_err());
+        assert!(parse_input_to_command("mask ###\nmask .##\n2 1\n0 0 N\nF").is_err());
+        assert!(
+            parse_input_to_command("polygon 0 0 2 0 2 1\nmask ###\nmask .##\n2 1\n1 0 N\nF")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_scent_policy_header() {
+        let command = parse_input_to_command("scent square\n5 3\n1 1 E\nF").unwrap();
+        assert_eq!(command.scent_policy, ScentPolicy::Square);
+        assert!(parse_input_to_command("scent fuzzy\n5 3\n1 1 E\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_decay_header() {
+        let command = parse_input_to_command("decay robots 3\n5 3\n1 1 E\nF").unwrap();
+        assert_eq!(command.scent_decay, Some(ScentDecay::Robots(3)));
+        let command = parse_input_to_command("decay ticks 20\n5 3\n1 1 E\nF").unwrap();
+        assert_eq!(command.scent_decay, Some(ScentDecay::Ticks(20)));
+        assert!(parse_input_to_command("decay days 3\n5 3\n1 1 E\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_collision_header() {
+        let command = parse_input_to_command("on-collision destroy\n5 3\n1 1 E\nF").unwrap();
+        assert_eq!(command.collision_policy, CollisionPolicy::Destroy);
+        assert!(parse_input_to_command("on-collision bounce\n5 3\n1 1 E\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_energy_header() {
+        let command =
+            parse_input_to_command("energy 20\ncost forward 2\n5 3\n1 1 E\nF\n\n2 2 N\nL").unwrap();
+        assert_eq!(command.energy, Some(20));
+        assert_eq!(command.energy_costs.forward, 2);
+        assert!(command.robots.iter().all(|robot| robot.energy == Some(20)));
+        assert!(parse_input_to_command("energy lots\n5 3\n1 1 E\nF").is_err());
+    }
+
+    #[test]
+    fn test_parse_named_robots() {
+        let str =
+            "model mk2\n5 3\n@rover-7 model=mk2 team=north\n1 1 E\nRFRFRFRF\n\n3 2 N\nF\n@scout\n0 3 W\n";
+
+        let command = parse_input_to_command(str).unwrap();
+
+        assert_eq!(command.robots.len(), 3);
+        assert_eq!(command.robots[0].id, Some("rover-7".to_string()));
+        assert_eq!(
+            command.robots[0].metadata,
+            vec![
+                ("model".to_string(), "mk2".to_string()),
+                ("team".to_string(), "north".to_string())
+            ]
+        );
+        assert_eq!(command.robots[0].line, 5);
+        assert_eq!(command.robots[1].id, None);
+        assert_eq!(command.robots[2].id, Some("scout".to_string()));
+        assert_eq!(command.robots[2].line, 10);
+        assert!(command.robots[2].robot_commands.is_empty());
+    }
+
+    #[rstest]
+    #[case(
+        "5 3\n@rover-7\n1 1 E\nF\n@rover-7\n2 2 N\nF",
+        "Robot id rover-7 is used more than once"
+    )]
+    #[case(
+        "5 3\n@rover-7 mk2\n1 1 E\nF",
+        "Robot rover-7 metadata must be key=value, got mk2"
+    )]
+    #[case("5 3\n@rover-7\n@rover-8\n1 1 E\nF", "Robot rover-7 has no position")]
+    #[case("5 3\n@\n1 1 E\nF", "A robot tag needs an id after the @")]
+    #[case(
+        "obstacle 1 1\n5 3\n@rover-7\n1 1 E\nF",
+        "Robot rover-7 dropped on an obstacle at 1 1 E"
+    )]
+    #[case(
+        "5 3\n@rover-7\n1 1 E\nFrF",
+        "Robot rover-7: half-turn commands need the 8-way compass (compass 8)"
+    )]
+    #[case(
+        "5 3\n@rover-7 model=mk9\n1 1 E\nF",
+        "Robot rover-7: unknown model mk9"
+    )]
+    #[case(
+        "model mk2 commands=FR\n5 3\n@rover-7 model=mk2\n1 1 E\nFRFL",
+        "Robot rover-7: model mk2 can't carry out L"
+    )]
+    #[case(
+        "model mk2\nmodel mk2 scents=ignore\n5 3\n1 1 E\nF",
+        "Model mk2 is declared more than once"
+    )]
+    fn test_named_robot_errors(#[case] input: &str, #[case] message: &str) {
+        assert_eq!(
+            parse_input_to_command(input).unwrap_err().to_string(),
+            message
+        );
+    }
 }
@@ -0,0 +1,259 @@
+use crate::energy::EnergyCosts;
+use crate::mission_instructions::{Compass, Coordinates, Orientation, RobotCommands};
+use crate::plateau::{BoundaryPolicy, Plateau};
+use crate::robots::{RobotPosition, RobotStatus};
+use crate::scents::{KnownScents, ScentStore};
+use anyhow::anyhow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a plan has to get to: a square, and optionally which way to face
+/// once there.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Goal {
+    pub coordinates: Coordinates,
+    pub orientation: Option<Orientation>,
+}
+
+impl Goal {
+    pub fn reached(&self, position: &RobotPosition) -> bool {
+        position.coordinates == self.coordinates
+            && self
+                .orientation
+                .as_ref()
+                .is_none_or(|orientation| *orientation == position.orientation)
+    }
+}
+
+impl FromStr for Goal {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split(' ').count() {
+            2 => Ok(Goal {
+                coordinates: Coordinates::from_str(input)?,
+                orientation: None,
+            }),
+            3 => {
+                let position = RobotPosition::from_str(input)?;
+                Ok(Goal {
+                    coordinates: position.coordinates,
+                    orientation: Some(position.orientation),
+                })
+            }
+            _ => Err(anyhow!("A goal is x y or x y heading, got {}", input)),
+        }
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.coordinates.x, self.coordinates.y)?;
+        if let Some(orientation) = &self.orientation {
+            write!(f, " {}", orientation)?;
+        }
+        Ok(())
+    }
+}
+
+/// The fewest squares between two points, ignoring obstacles, going round the
+/// edges when the plateau wraps.
+fn distance(from: &Coordinates, to: &Coordinates, plateau: &Plateau, compass: &Compass) -> u32 {
+    let span = |a: i32, b: i32, size: i32| {
+        let straight = (a - b).abs();
+        if plateau.boundary == BoundaryPolicy::Wrap {
+            straight.min(size - straight)
+        } else {
+            straight
+        }
+    };
+    let dx = span(from.x, to.x, plateau.bounds.width());
+    let dy = span(from.y, to.y, plateau.bounds.height());
+    match compass {
+        Compass::FourWay => (dx + dy) as u32,
+        Compass::EightWay => dx.max(dy) as u32,
+    }
+}
+
+/// Finds the cheapest string of commands that takes a robot from `start` to
+/// `goal` without ever losing or crashing it, using A* over (square, heading)
+/// states. Moves that a scent or an obstacle would ignore are never used, and
+/// `None` means the goal can't be reached.
+pub fn plan<S: ScentStore>(
+    plateau: &Plateau,
+    compass: &Compass,
+    start: &RobotPosition,
+    goal: &Goal,
+    scents: &S,
+    costs: &EnergyCosts,
+) -> Option<Vec<RobotCommands>> {
+    let commands: Vec<RobotCommands> = [
+        RobotCommands::Forward,
+        RobotCommands::Left,
+        RobotCommands::Right,
+        RobotCommands::HalfLeft,
+        RobotCommands::HalfRight,
+    ]
+    .into_iter()
+    .filter(|command| compass.supports_command(command))
+    .collect();
+    let estimate = |position: &RobotPosition| {
+        distance(&position.coordinates, &goal.coordinates, plateau, compass) * costs.forward
+    };
+
+    let mut known_scents = KnownScents(scents);
+    let mut best: HashMap<RobotPosition, (u32, Option<(RobotPosition, RobotCommands)>)> =
+        HashMap::new();
+    let mut positions = vec![start.clone()];
+    let mut queue = BinaryHeap::new();
+    best.insert(start.clone(), (0, None));
+    queue.push(Reverse((estimate(start), 0)));
+
+    while let Some(Reverse((_, index))) = queue.pop() {
+        let position = positions[index].clone();
+        let cost = best[&position].0;
+        if goal.reached(&position) {
+            let mut route = vec![];
+            let mut step = &position;
+            while let Some((previous, command)) = &best[step].1 {
+                route.push(command.clone());
+                step = previous;
+            }
+            route.reverse();
+            return Some(route);
+        }
+
+        for command in &commands {
+            let (next, status) = command.process(position.clone(), plateau, &mut known_scents);
+            if status != RobotStatus::Alive || next == position {
+                continue;
+            }
+            let next_cost = cost + costs.used(command, false);
+            if best
+                .get(&next)
+                .is_some_and(|(known, _)| *known <= next_cost)
+            {
+                continue;
+            }
+            best.insert(
+                next.clone(),
+                (next_cost, Some((position.clone(), command.clone()))),
+            );
+            queue.push(Reverse((next_cost + estimate(&next), positions.len())));
+            positions.push(next);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::energy::EnergyCosts;
+    use crate::mission_instructions::{Compass, Coordinates, RobotCommands};
+    use crate::parser::parse_input_to_command;
+    use crate::planner::{plan, Goal};
+    use crate::robots::{Robot, RobotPosition, RobotStatus};
+    use rstest::*;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn plan_string(mission: &str, start: &str, goal: &str, costs: &EnergyCosts) -> Option<String> {
+        let command = parse_input_to_command(mission).unwrap();
+        let scents: HashSet<RobotPosition> = HashSet::new();
+        plan(
+            &command.plateau(),
+            &command.compass,
+            &RobotPosition::from_str(start).unwrap(),
+            &Goal::from_str(goal).unwrap(),
+            &scents,
+            costs,
+        )
+        .map(|route| route.iter().map(|command| command.to_string()).collect())
+    }
+
+    #[rstest]
+    #[case("5 3\n", "1 1 E", "3 1", "FF")]
+    #[case("5 3\n", "1 1 E", "1 1 W", "LL")]
+    #[case("5 3\n", "0 0 N", "2 2 E", "FFRFF")]
+    #[case("obstacle 1 0 1 2\n5 3\n", "0 0 E", "2 0", "LFFFRFFRFFF")]
+    #[case("boundary wrap\n5 3\n", "0 0 W", "5 0", "F")]
+    #[case("compass 8\n5 3\n", "0 0 N", "3 3", "rFFF")]
+    fn test_plan(
+        #[case] mission: &str,
+        #[case] start: &str,
+        #[case] goal: &str,
+        #[case] route: &str,
+    ) {
+        assert_eq!(
+            plan_string(mission, start, goal, &EnergyCosts::default()),
+            Some(route.to_string())
+        );
+    }
+
+    #[test]
+    fn test_plan_weighs_turns() {
+        let costs = EnergyCosts {
+            forward: 1,
+            turn: 10,
+            blocked: 1,
+        };
+
+        // with cheap forward moves it goes round the obstacle with the fewest turns
+        assert_eq!(
+            plan_string("obstacle 1 1\n5 3\n", "0 0 N", "2 2", &costs),
+            Some("FFRFF".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        assert_eq!(
+            plan_string(
+                "obstacle 1 0 1 3\n5 3\n",
+                "0 0 N",
+                "3 3",
+                &EnergyCosts::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_plan_avoids_scented_moves() {
+        let command = parse_input_to_command("5 3\n").unwrap();
+        let plateau = command.plateau();
+        let mut scents: HashSet<RobotPosition> = HashSet::new();
+        scents.insert(RobotPosition::from_str("1 1 N").unwrap());
+
+        let route = plan(
+            &plateau,
+            &Compass::FourWay,
+            &RobotPosition::from_str("1 0 N").unwrap(),
+            &Goal::from_str("1 3").unwrap(),
+            &scents,
+            &EnergyCosts::default(),
+        )
+        .unwrap();
+
+        // driving the route for real gets there and nothing is lost
+        let robot = Robot::new()
+            .set_start_position(RobotPosition::from_str("1 0 N").unwrap())
+            .update_commands(route.clone())
+            .process_all_commands(&plateau, &mut scents);
+        assert_eq!(robot.robot_status, RobotStatus::Alive);
+        assert_eq!(robot.position.coordinates, Coordinates { x: 1, y: 3 });
+        assert_ne!(route, vec![RobotCommands::Forward; 3]);
+    }
+
+    #[rstest]
+    #[case("3 2", Goal { coordinates: Coordinates { x: 3, y: 2 }, orientation: None })]
+    fn test_goal_from_str(#[case] input: &str, #[case] expected: Goal) {
+        assert_eq!(Goal::from_str(input).unwrap(), expected);
+        assert_eq!(expected.to_string(), input);
+        assert_eq!(Goal::from_str("3 2 N").unwrap().to_string(), "3 2 N");
+        assert!(Goal::from_str("3").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+use crate::mission_instructions::{Coordinates, GridBounds};
+use crate::scents::ScentPolicy;
+use anyhow::anyhow;
+use std::fmt;
+use std::str::FromStr;
+
+/// A blocked rectangle of squares, inclusive on both corners. A single rock is
+/// just a rectangle where both corners are the same square.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Obstacle {
+    pub lower_left: Coordinates,
+    pub upper_right: Coordinates,
+}
+
+impl Obstacle {
+    pub fn contains(&self, coordinates: &Coordinates) -> bool {
+        coordinates.x >= self.lower_left.x
+            && coordinates.x <= self.upper_right.x
+            && coordinates.y >= self.lower_left.y
+            && coordinates.y <= self.upper_right.y
+    }
+}
+
+impl FromStr for Obstacle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split_whitespace()
+            .map(|value| value.parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        let (lower_left, upper_right) = match values[..] {
+            [x, y] => (Coordinates { x, y }, Coordinates { x, y }),
+            [x1, y1, x2, y2] => (
+                Coordinates {
+                    x: x1.min(x2),
+                    y: y1.min(y2),
+                },
+                Coordinates {
+                    x: x1.max(x2),
+                    y: y1.max(y2),
+                },
+            ),
+            _ => return Err(anyhow!("Obstacles need either 2 or 4 numbers: {}", s)),
+        };
+
+        Ok(Obstacle {
+            lower_left,
+            upper_right,
+        })
+    }
+}
+
+/// What happens to a robot that tries to drive into an obstacle.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ObstaclePolicy {
+    #[default]
+    Stay,
+    Crash,
+}
+
+impl FromStr for ObstaclePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "stay" => Ok(ObstaclePolicy::Stay),
+            "crash" => Ok(ObstaclePolicy::Crash),
+            _ => Err(anyhow!(
+                "Obstacle policy must be stay or crash, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ObstaclePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObstaclePolicy::Stay => write!(f, "stay"),
+            ObstaclePolicy::Crash => write!(f, "crash"),
+        }
+    }
+}
+
+/// The safe ground inside the grid's bounding box when the plateau isn't a
+/// rectangle. Leaving it is treated the same as going over the grid edge.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Region {
+    /// Corners of the plateau in order. Squares on the outline count as inside.
+    Polygon(Vec<Coordinates>),
+    /// One row per line of the grid, north first, `true` where there is ground.
+    Mask(Vec<Vec<bool>>),
+}
+
+impl Region {
+    pub fn polygon_from_str(s: &str) -> Result<Self, anyhow::Error> {
+        let values = s
+            .split_whitespace()
+            .map(|value| value.parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        if values.len() % 2 != 0 || values.len() < 6 {
+            return Err(anyhow!(
+                "Polygons need at least 3 corners given as x y pairs: {}",
+                s
+            ));
+        }
+
+        Ok(Region::Polygon(
+            values
+                .chunks(2)
+                .map(|pair| Coordinates {
+                    x: pair[0],
+                    y: pair[1],
+                })
+                .collect(),
+        ))
+    }
+
+    /// Mask rows use `#` for ground and `.` for a drop, and have to cover the
+    /// whole grid.
+    pub fn mask_from_rows(rows: &[String], bounds: &GridBounds) -> Result<Self, anyhow::Error> {
+        if rows.len() as i32 != bounds.height() {
+            return Err(anyhow!(
+                "Mask has {} rows but the grid is {} high",
+                rows.len(),
+                bounds.height()
+            ));
+        }
+
+        let mask = rows
+            .iter()
+            .map(|row| {
+                if row.chars().count() as i32 != bounds.width() {
+                    return Err(anyhow!("Mask row {} isn't {} wide", row, bounds.width()));
+                }
+                row.chars()
+                    .map(|c| match c {
+                        '#' => Ok(true),
+                        '.' => Ok(false),
+                        _ => Err(anyhow!("Mask rows can only contain # or . got {}", c)),
+                    })
+                    .collect::<Result<Vec<bool>, anyhow::Error>>()
+            })
+            .collect::<Result<Vec<Vec<bool>>, anyhow::Error>>()?;
+
+        Ok(Region::Mask(mask))
+    }
+
+    pub fn contains(&self, coordinates: &Coordinates, bounds: &GridBounds) -> bool {
+        if coordinates.fallen_off_grid(bounds) {
+            return false;
+        }
+        match self {
+            Region::Polygon(corners) => polygon_contains(corners, coordinates),
+            Region::Mask(rows) => {
+                let row = (bounds.upper_right.y - coordinates.y) as usize;
+                let column = (coordinates.x - bounds.lower_left.x) as usize;
+                rows[row][column]
+            }
+        }
+    }
+}
+
+/// Even-odd ray cast, with an extra check so that squares sitting exactly on
+/// the outline count as inside.
+fn polygon_contains(corners: &[Coordinates], point: &Coordinates) -> bool {
+    let mut inside = false;
+    for (index, a) in corners.iter().enumerate() {
+        let b = &corners[(index + 1) % corners.len()];
+
+        let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+        if cross == 0
+            && point.x >= a.x.min(b.x)
+            && point.x <= a.x.max(b.x)
+            && point.y >= a.y.min(b.y)
+            && point.y <= a.y.max(b.y)
+        {
+            return true;
+        }
+
+        if (a.y > point.y) != (b.y > point.y) {
+            let lhs = (point.x - a.x) * (b.y - a.y);
+            let rhs = (point.y - a.y) * (b.x - a.x);
+            if (b.y > a.y && lhs < rhs) || (b.y < a.y && lhs > rhs) {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// What the edge of the grid does to a robot that drives over it.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BoundaryPolicy {
+    /// The robot falls off and leaves a scent behind.
+    #[default]
+    Lost,
+    /// The robot comes back in on the opposite edge.
+    Wrap,
+    /// The robot bumps into the edge and stays where it is.
+    Clamp,
+}
+
+impl FromStr for BoundaryPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "lost" => Ok(BoundaryPolicy::Lost),
+            "wrap" => Ok(BoundaryPolicy::Wrap),
+            "clamp" => Ok(BoundaryPolicy::Clamp),
+            _ => Err(anyhow!(
+                "Boundary policy must be lost, wrap or clamp, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BoundaryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundaryPolicy::Lost => write!(f, "lost"),
+            BoundaryPolicy::Wrap => write!(f, "wrap"),
+            BoundaryPolicy::Clamp => write!(f, "clamp"),
+        }
+    }
+}
+
+/// Everything a robot needs to know about the ground it drives on, and the
+/// rules for what happens at its edges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plateau {
+    pub bounds: GridBounds,
+    pub obstacles: Vec<Obstacle>,
+    pub obstacle_policy: ObstaclePolicy,
+    pub boundary: BoundaryPolicy,
+    pub region: Option<Region>,
+    pub scent_policy: ScentPolicy,
+}
+
+impl Plateau {
+    pub fn new(bounds: GridBounds) -> Self {
+        Plateau {
+            bounds,
+            obstacles: vec![],
+            obstacle_policy: ObstaclePolicy::default(),
+            boundary: BoundaryPolicy::default(),
+            region: None,
+            scent_policy: ScentPolicy::default(),
+        }
+    }
+
+    pub fn with_obstacles(self, obstacles: Vec<Obstacle>, obstacle_policy: ObstaclePolicy) -> Self {
+        Plateau {
+            obstacles,
+            obstacle_policy,
+            ..self
+        }
+    }
+
+    pub fn with_boundary(self, boundary: BoundaryPolicy) -> Self {
+        Plateau { boundary, ..self }
+    }
+
+    pub fn with_region(self, region: Option<Region>) -> Self {
+        Plateau { region, ..self }
+    }
+
+    pub fn with_scent_policy(self, scent_policy: ScentPolicy) -> Self {
+        Plateau {
+            scent_policy,
+            ..self
+        }
+    }
+
+    /// True when the square is over the grid edge or outside the plateau's
+    /// outline.
+    pub fn off_plateau(&self, coordinates: &Coordinates) -> bool {
+        match &self.region {
+            Some(region) => !region.contains(coordinates, &self.bounds),
+            None => coordinates.fallen_off_grid(&self.bounds),
+        }
+    }
+
+    pub fn is_blocked(&self, coordinates: &Coordinates) -> bool {
+        self.obstacles
+            .iter()
+            .any(|obstacle| obstacle.contains(coordinates))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mission_instructions::{Coordinates, GridBounds};
+    use crate::plateau::{Obstacle, ObstaclePolicy, Plateau, Region};
+    use rstest::*;
+    use std::str::FromStr;
+
+    #[rstest]
+    #[case("2 2", Coordinates { x: 2, y: 2 }, Coordinates { x: 2, y: 2 })]
+    #[case("1 0 3 2", Coordinates { x: 1, y: 0 }, Coordinates { x: 3, y: 2 })]
+    #[case("3 2 1 0", Coordinates { x: 1, y: 0 }, Coordinates { x: 3, y: 2 })]
+    fn test_obstacle_from_str(
+        #[case] input: &str,
+        #[case] lower_left: Coordinates,
+        #[case] upper_right: Coordinates,
+    ) {
+        let obstacle = Obstacle::from_str(input).unwrap();
+        assert_eq!(
+            obstacle,
+            Obstacle {
+                lower_left,
+                upper_right
+            }
+        )
+    }
+
+    #[rstest]
+    #[case("1")]
+    #[case("1 2 3")]
+    #[case("a b")]
+    fn test_obstacle_err_from_str(#[case] input: &str) {
+        assert!(Obstacle::from_str(input).is_err())
+    }
+
+    #[test]
+    fn test_is_blocked() {
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into()).with_obstacles(
+            vec![Obstacle::from_str("1 1 2 2").unwrap()],
+            ObstaclePolicy::Stay,
+        );
+
+        assert!(plateau.is_blocked(&Coordinates { x: 1, y: 1 }));
+        assert!(plateau.is_blocked(&Coordinates { x: 2, y: 2 }));
+        assert!(!plateau.is_blocked(&Coordinates { x: 3, y: 2 }));
+        assert!(!plateau.is_blocked(&Coordinates { x: 0, y: 0 }));
+    }
+
+    #[rstest]
+    #[case(0, 0, true)]
+    #[case(4, 0, true)]
+    #[case(2, 2, true)]
+    #[case(4, 4, true)]
+    #[case(0, 4, false)]
+    #[case(1, 3, false)]
+    #[case(5, 0, false)]
+    fn test_polygon_region(#[case] x: i32, #[case] y: i32, #[case] inside: bool) {
+        // right angled triangle with the hypotenuse running corner to corner
+        let region = Region::polygon_from_str("0 0 4 0 4 4").unwrap();
+        let bounds = GridBounds::from_str("4 4").unwrap();
+
+        assert_eq!(region.contains(&Coordinates { x, y }, &bounds), inside)
+    }
+
+    #[rstest]
+    #[case(0, 0, false)]
+    #[case(1, 0, true)]
+    #[case(0, 2, true)]
+    #[case(2, 2, false)]
+    #[case(3, 1, false)]
+    fn test_mask_region(#[case] x: i32, #[case] y: i32, #[case] inside: bool) {
+        let rows = vec!["##.".to_string(), "###".to_string(), ".##".to_string()];
+        let bounds = GridBounds::from_str("2 2").unwrap();
+        let region = Region::mask_from_rows(&rows, &bounds).unwrap();
+
+        assert_eq!(region.contains(&Coordinates { x, y }, &bounds), inside)
+    }
+
+    #[test]
+    fn test_region_errors() {
+        let bounds = GridBounds::from_str("2 2").unwrap();
+        assert!(Region::polygon_from_str("0 0 4 0").is_err());
+        assert!(Region::polygon_from_str("0 0 4 0 4").is_err());
+        assert!(Region::mask_from_rows(&["###".to_string()], &bounds).is_err());
+        assert!(Region::mask_from_rows(
+            &["###".to_string(), "##".to_string(), "###".to_string()],
+            &bounds
+        )
+        .is_err());
+        assert!(Region::mask_from_rows(
+            &["###".to_string(), "#x#".to_string(), "###".to_string()],
+            &bounds
+        )
+        .is_err());
+    }
+}
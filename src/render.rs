@@ -0,0 +1,537 @@
+use crate::analysis::{Danger, DangerMap};
+use crate::heatmap::Heatmap;
+use crate::mission_instructions::{Coordinates, GridBounds, Orientation};
+use crate::report::MissionReport;
+use crate::robots::{Robot, RobotStatus};
+use crate::solver::Edge;
+
+const CELL_SIZE: i32 = 40;
+
+fn heading_glyph(orientation: &Orientation) -> char {
+    match orientation {
+        Orientation::North => '^',
+        Orientation::East => '>',
+        Orientation::South => 'v',
+        Orientation::West => '<',
+        Orientation::NorthEast | Orientation::SouthWest => '/',
+        Orientation::NorthWest | Orientation::SouthEast => '\\',
+    }
+}
+
+fn robot_glyph(robot: &Robot) -> char {
+    match robot.robot_status {
+        RobotStatus::Alive => heading_glyph(&robot.position.orientation),
+        RobotStatus::Lost => 'X',
+        RobotStatus::Crashed => '!',
+        RobotStatus::Collided => '%',
+        RobotStatus::Depleted => 'z',
+    }
+}
+
+/// Draws the plateau top row first so that north is up, the same way the
+/// grid is described in the mission.
+pub fn ascii(report: &MissionReport) -> String {
+    let plateau = report.command.plateau();
+    let bounds = &plateau.bounds;
+    let mut output = String::new();
+
+    for y in (bounds.lower_left.y..=bounds.upper_right.y).rev() {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            let robot = report
+                .robots
+                .iter()
+                .rev()
+                .find(|robot| robot.position.coordinates == square);
+            let glyph = if let Some(robot) = robot {
+                robot_glyph(robot)
+            } else if plateau.off_plateau(&square) {
+                ' '
+            } else if plateau.is_blocked(&square) {
+                '#'
+            } else if report
+                .scents
+                .iter()
+                .any(|scent| scent.position.coordinates == square)
+            {
+                '*'
+            } else {
+                '.'
+            };
+            output.push(glyph);
+        }
+        output.push('\n');
+    }
+    output.push_str(
+        "# obstacle  * scent  X lost  ! crashed  % collided  z depleted  ^>v< robot heading\n",
+    );
+    output.push_str(&format!("boundary: {}\n", plateau.boundary));
+    // named robots are listed so they can be told apart on the map
+    if report.robots.iter().any(|robot| robot.id.is_some()) {
+        for (index, robot) in report.robots.iter().enumerate() {
+            output.push_str(&format!(
+                "{} {}: {} {}\n",
+                robot_glyph(robot),
+                robot.label(index),
+                robot.position,
+                robot.robot_status
+            ));
+        }
+    }
+    for saved_by in &report.saved_by {
+        output.push_str(&format!("{}\n", report.saved_by_message(saved_by)));
+    }
+    for collision in &report.collisions {
+        output.push_str(&format!("{}\n", report.collision_message(collision)));
+    }
+    output
+}
+
+/// The top left pixel of a square, SVG counts y downwards from the top.
+fn svg_corner(coordinates: &Coordinates, bounds: &GridBounds) -> (i32, i32) {
+    (
+        (coordinates.x - bounds.lower_left.x) * CELL_SIZE,
+        (bounds.upper_right.y - coordinates.y) * CELL_SIZE,
+    )
+}
+
+fn svg_point(coordinates: &Coordinates, bounds: &GridBounds) -> (i32, i32) {
+    let (x, y) = svg_corner(coordinates, bounds);
+    (x + CELL_SIZE / 2, y + CELL_SIZE / 2)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn svg(report: &MissionReport) -> String {
+    let plateau = report.command.plateau();
+    let bounds = &plateau.bounds;
+    let width = bounds.width() * CELL_SIZE;
+    let height = bounds.height() * CELL_SIZE;
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+
+    for y in bounds.lower_left.y..=bounds.upper_right.y {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            let (left, top) = svg_corner(&square, bounds);
+            let fill = if plateau.off_plateau(&square) {
+                "#1a1a1a"
+            } else {
+                "#c1440e"
+            };
+            output.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#7a2a08\"/>\n",
+                left, top, CELL_SIZE, CELL_SIZE, fill
+            ));
+        }
+    }
+
+    for obstacle in &report.command.obstacles {
+        let top_left = Coordinates {
+            x: obstacle.lower_left.x,
+            y: obstacle.upper_right.y,
+        };
+        let (left, top) = svg_corner(&top_left, bounds);
+        output.push_str(&format!(
+            "  <rect class=\"obstacle\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#3b2a20\"/>\n",
+            left,
+            top,
+            (obstacle.upper_right.x - obstacle.lower_left.x + 1) * CELL_SIZE,
+            (obstacle.upper_right.y - obstacle.lower_left.y + 1) * CELL_SIZE
+        ));
+    }
+
+    for scent in &report.scents {
+        let (cx, cy) = svg_point(&scent.position.coordinates, bounds);
+        let (dx, dy) = scent.position.orientation.delta();
+        output.push_str(&format!(
+            "  <circle class=\"scent\" cx=\"{}\" cy=\"{}\" r=\"4\" fill=\"#f5d142\"/>\n",
+            cx + dx * CELL_SIZE / 3,
+            cy - dy * CELL_SIZE / 3
+        ));
+    }
+
+    for (index, robot) in report.robots.iter().enumerate() {
+        let (cx, cy) = svg_point(&robot.position.coordinates, bounds);
+        let (dx, dy) = robot.position.orientation.delta();
+        let fill = match robot.robot_status {
+            RobotStatus::Alive => "#2e8b57",
+            RobotStatus::Lost => "#d62728",
+            RobotStatus::Crashed => "#111111",
+            RobotStatus::Collided => "#7f3fbf",
+            RobotStatus::Depleted => "#8c8c8c",
+        };
+        output.push_str(&format!(
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"3\"/>\n",
+            cx,
+            cy,
+            cx + dx * CELL_SIZE / 2,
+            cy - dy * CELL_SIZE / 2,
+            fill
+        ));
+        output.push_str(&format!(
+            "  <circle class=\"robot\" cx=\"{}\" cy=\"{}\" r=\"12\" fill=\"{}\"/>\n",
+            cx, cy, fill
+        ));
+        output.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\" fill=\"#ffffff\">{}</text>\n",
+            cx,
+            cy + 4,
+            xml_escape(&robot.label(index))
+        ));
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+/// Marks each square with how many headings drive straight to a loss or a
+/// crash, or `*` when the only ways off it are covered by scents.
+pub fn danger_ascii(map: &DangerMap) -> String {
+    let plateau = &map.plateau;
+    let bounds = &plateau.bounds;
+    let mut output = String::new();
+
+    for y in (bounds.lower_left.y..=bounds.upper_right.y).rev() {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            let fatal = map
+                .at(&square)
+                .filter(|(_, danger)| *danger == Danger::Fatal)
+                .count();
+            let glyph = if plateau.off_plateau(&square) {
+                ' '
+            } else if plateau.is_blocked(&square) {
+                '#'
+            } else if fatal > 0 {
+                char::from_digit(fatal as u32, 10).unwrap_or('+')
+            } else if map
+                .at(&square)
+                .any(|(_, danger)| *danger == Danger::Protected)
+            {
+                '*'
+            } else {
+                '.'
+            };
+            output.push(glyph);
+        }
+        output.push('\n');
+    }
+    output.push_str("# obstacle  * scent-protected  1-8 fatal headings  . safe\n");
+    output.push_str(&format!(
+        "minimum losses to scent the perimeter: {}\n",
+        map.minimum_losses
+    ));
+    output
+}
+
+/// Draws the plateau with a red tick pointing each fatal way off a square and
+/// a yellow one for each way a scent already covers.
+pub fn danger_svg(map: &DangerMap) -> String {
+    let plateau = &map.plateau;
+    let bounds = &plateau.bounds;
+    let width = bounds.width() * CELL_SIZE;
+    let height = bounds.height() * CELL_SIZE;
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+
+    for y in bounds.lower_left.y..=bounds.upper_right.y {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            let (left, top) = svg_corner(&square, bounds);
+            let fill = if plateau.off_plateau(&square) {
+                "#1a1a1a"
+            } else if plateau.is_blocked(&square) {
+                "#3b2a20"
+            } else {
+                "#c1440e"
+            };
+            output.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#7a2a08\"/>\n",
+                left, top, CELL_SIZE, CELL_SIZE, fill
+            ));
+        }
+    }
+
+    for (position, danger) in &map.states {
+        let (class, stroke) = match danger {
+            Danger::Safe => continue,
+            Danger::Protected => ("protected", "#f5d142"),
+            Danger::Fatal => ("fatal", "#d62728"),
+        };
+        let (cx, cy) = svg_point(&position.coordinates, bounds);
+        let (dx, dy) = position.orientation.delta();
+        output.push_str(&format!(
+            "  <line class=\"{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"3\"/>\n",
+            class,
+            cx + dx * CELL_SIZE / 6,
+            cy - dy * CELL_SIZE / 6,
+            cx + dx * CELL_SIZE / 2,
+            cy - dy * CELL_SIZE / 2,
+            stroke
+        ));
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+/// Visit shading from least to most, after `.` for squares never visited.
+const SHADES: [char; 8] = [':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Shades each square by how often robots stood on it, relative to the
+/// busiest square, then lists the squares robots were lost from.
+pub fn heatmap_ascii(heatmap: &Heatmap) -> String {
+    let Some(bounds) = &heatmap.bounds else {
+        return "no missions\n".to_string();
+    };
+    let most = heatmap.most_visits();
+    let mut output = String::new();
+
+    for y in (bounds.lower_left.y..=bounds.upper_right.y).rev() {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let visits = heatmap.visits_at(&Coordinates { x, y });
+            let glyph = if visits == 0 {
+                '.'
+            } else {
+                SHADES[(visits * SHADES.len()).div_ceil(most) - 1]
+            };
+            output.push(glyph);
+        }
+        output.push('\n');
+    }
+    output.push_str(&format!(
+        ". never visited  {} fewest to most, busiest square {} visits\n",
+        SHADES.iter().collect::<String>(),
+        most
+    ));
+    for (((x, y), edge), lost) in &heatmap.losses {
+        output.push_str(&format!(
+            "{} lost from {} {} over the {}\n",
+            lost, x, y, edge
+        ));
+    }
+    output
+}
+
+/// Which way to nudge a loss marker so it sits against the edge crossed.
+fn edge_delta(edge: &Edge) -> (i32, i32) {
+    match edge {
+        Edge::North => (0, 1),
+        Edge::NorthEast => (1, 1),
+        Edge::East => (1, 0),
+        Edge::SouthEast => (1, -1),
+        Edge::South => (0, -1),
+        Edge::SouthWest => (-1, -1),
+        Edge::West => (-1, 0),
+        Edge::NorthWest => (-1, 1),
+        Edge::Outline => (0, 0),
+    }
+}
+
+/// Colours each square from pale for few visits to deep red for the most,
+/// with a black marker against the edge robots were lost over, sized by how
+/// many were.
+pub fn heatmap_svg(heatmap: &Heatmap) -> String {
+    let Some(bounds) = &heatmap.bounds else {
+        return "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\"/>\n"
+            .to_string();
+    };
+    let width = bounds.width() * CELL_SIZE;
+    let height = bounds.height() * CELL_SIZE;
+    let most = heatmap.most_visits().max(1);
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+
+    for y in bounds.lower_left.y..=bounds.upper_right.y {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            let (left, top) = svg_corner(&square, bounds);
+            let visits = heatmap.visits_at(&square);
+            // from #fff5eb for a single visit to #a50f15 for the busiest square
+            let heat = |cold: u8, hot: u8| {
+                let scale = if most == 1 {
+                    1.0
+                } else {
+                    (visits - 1) as f64 / (most - 1) as f64
+                };
+                (cold as f64 + (hot as f64 - cold as f64) * scale).round() as u8
+            };
+            let fill = if visits == 0 {
+                "#f0f0f0".to_string()
+            } else {
+                format!(
+                    "#{:02x}{:02x}{:02x}",
+                    heat(0xff, 0xa5),
+                    heat(0xf5, 0x0f),
+                    heat(0xeb, 0x15)
+                )
+            };
+            output.push_str(&format!(
+                "  <rect class=\"visits\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#cccccc\"><title>{} {}: {} visits</title></rect>\n",
+                left, top, CELL_SIZE, CELL_SIZE, fill, x, y, visits
+            ));
+        }
+    }
+
+    for (((x, y), edge), lost) in &heatmap.losses {
+        let (cx, cy) = svg_point(&Coordinates { x: *x, y: *y }, bounds);
+        let (dx, dy) = edge_delta(edge);
+        output.push_str(&format!(
+            "  <circle class=\"loss\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"#111111\"><title>{} lost over the {}</title></circle>\n",
+            cx + dx * CELL_SIZE / 3,
+            cy - dy * CELL_SIZE / 3,
+            (3 + *lost as i32).min(CELL_SIZE / 4),
+            lost,
+            edge
+        ));
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analysis::analyze;
+    use crate::heatmap::Heatmap;
+    use crate::parser::parse_input_to_command;
+    use crate::render::{ascii, danger_ascii, danger_svg, heatmap_ascii, heatmap_svg, svg};
+    use crate::report::MissionReport;
+    use crate::robots::RobotPosition;
+    use crate::scents::ScentLog;
+    use crate::simulation::{run_mission, Schedule};
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn sample_report_input() -> &'static str {
+        "obstacle 2 2\nobstacle 4 0 4 1\n5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n0 3 W\nLLFFFLFLFL"
+    }
+
+    #[test]
+    fn test_ascii_render() {
+        let command = parse_input_to_command(sample_report_input()).unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(ascii(&report))
+    }
+
+    #[test]
+    fn test_ascii_render_region() {
+        let command =
+            parse_input_to_command("polygon 0 0 4 0 4 4\n4 4\n2 1 N\nFFFF\n\n3 2 E\nF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(ascii(&report))
+    }
+
+    #[test]
+    fn test_svg_render() {
+        let command = parse_input_to_command(sample_report_input()).unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+        let svg = svg(&report);
+
+        assert_eq!(svg.matches("class=\"obstacle\"").count(), 2);
+        assert_eq!(svg.matches("class=\"robot\"").count(), 3);
+        assert_eq!(svg.matches("class=\"scent\"").count(), 1);
+    }
+
+    #[test]
+    fn test_ascii_render_named_robots() {
+        let command = parse_input_to_command(
+            "model mk2\n5 3\n@rover-7 model=mk2\n1 1 E\nF\n\n3 3 N\nF\n@scout\n3 3 N\nF",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(ascii(&report))
+    }
+
+    #[test]
+    fn test_svg_labels_named_robots() {
+        let command = parse_input_to_command("5 3\n@<rover>\n1 1 E\nF\n\n2 2 N\nF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+        let svg = svg(&report);
+
+        assert!(svg.contains(">&lt;rover&gt;</text>"));
+        assert!(svg.contains(">1</text>"));
+    }
+
+    #[test]
+    fn test_danger_ascii_render() {
+        let command = parse_input_to_command("obstacle 2 1\n4 2\n").unwrap();
+        let scents: HashSet<RobotPosition> = [RobotPosition::from_str("4 2 N").unwrap()]
+            .into_iter()
+            .collect();
+
+        let map = analyze(&command.plateau(), &command.compass, &scents);
+
+        insta::assert_display_snapshot!(danger_ascii(&map))
+    }
+
+    #[test]
+    fn test_danger_svg_render() {
+        let command = parse_input_to_command("2 1\n").unwrap();
+        let scents: HashSet<RobotPosition> = [RobotPosition::from_str("0 0 W").unwrap()]
+            .into_iter()
+            .collect();
+
+        let map = analyze(&command.plateau(), &command.compass, &scents);
+        let svg = danger_svg(&map);
+
+        assert_eq!(svg.matches("class=\"fatal\"").count(), 9);
+        assert_eq!(svg.matches("class=\"protected\"").count(), 1);
+    }
+
+    fn sample_heatmap() -> Heatmap {
+        let mut heatmap = Heatmap::default();
+        for mission in [sample_report_input(), "2 1\n0 0 E\nFFLF\n\n2 1 N\nF"] {
+            let command = parse_input_to_command(mission).unwrap();
+            let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+            let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+            heatmap.add(&command, &outcome);
+        }
+        heatmap
+    }
+
+    #[test]
+    fn test_heatmap_ascii_render() {
+        insta::assert_display_snapshot!(heatmap_ascii(&sample_heatmap()))
+    }
+
+    #[test]
+    fn test_heatmap_svg_render() {
+        let svg = heatmap_svg(&sample_heatmap());
+
+        assert_eq!(svg.matches("class=\"visits\"").count(), 24);
+        assert_eq!(svg.matches("class=\"loss\"").count(), 2);
+        assert!(svg.contains("fill=\"#a50f15\""));
+        assert!(svg.contains("fill=\"#f0f0f0\""));
+    }
+}
@@ -0,0 +1,367 @@
+use crate::cli::ScentStoreKind;
+use crate::mission_instructions::Command;
+use crate::parser::parse_input_to_command;
+use crate::robots::RobotPosition;
+use crate::scents::{BitsetScentStore, ScentLog, ScentStore};
+use crate::simulation::{run_mission, Schedule, Step};
+use anyhow::anyhow;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// FNV-1a over the mission's bytes. Unlike the std hashers its output is
+/// fixed, so logs written by one build can be checked by another.
+pub fn input_hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Everything needed to run a mission again exactly as it went, and what
+/// happened on every tick when it did. Written out as
+/// ```text
+/// # martian-robots replay
+/// version 0.1.0
+/// input 9ae1c0e7b5d3f2a4
+/// schedule sequential
+/// scent-store memory
+/// known 0 3 W
+/// tick 0 robot 0 R -> 1 1 S ALIVE
+/// tick 15 robot 1 F -> 3 3 N LOST scent 3 3 N
+/// ```
+/// `known` lines are the scents the store held before the mission started,
+/// e.g. from a scent file, which will have changed since. The log doesn't say
+/// how old they were, so missions with `decay` can only be checked when there
+/// weren't any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayLog {
+    /// The version of martian-robots that recorded the log.
+    pub version: String,
+    pub input_hash: u64,
+    pub schedule: Schedule,
+    pub scent_store: ScentStoreKind,
+    pub known_scents: Vec<RobotPosition>,
+    pub steps: Vec<Step>,
+}
+
+/// The first tick where a replay and its log disagree. Either side is `None`
+/// when it ran out of ticks before the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub logged: Option<Step>,
+    pub replayed: Option<Step>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |step: &Option<Step>| match step {
+            Some(step) => step.to_string(),
+            None => "nothing, the mission had finished".to_string(),
+        };
+        writeln!(f, "logged:   {}", describe(&self.logged))?;
+        write!(f, "replayed: {}", describe(&self.replayed))
+    }
+}
+
+/// What replaying a log found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOutcome {
+    /// The version the log was recorded with, when it isn't this one.
+    pub recorded_with: Option<String>,
+    /// Ticks in the log.
+    pub ticks: usize,
+    pub divergence: Option<Divergence>,
+}
+
+impl fmt::Display for ReplayOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(version) = &self.recorded_with {
+            writeln!(
+                f,
+                "Log recorded with version {}, replayed with {}",
+                version,
+                env!("CARGO_PKG_VERSION")
+            )?;
+        }
+        match &self.divergence {
+            None => write!(f, "All {} ticks match", self.ticks),
+            Some(divergence) => write!(f, "{}", divergence),
+        }
+    }
+}
+
+impl ReplayLog {
+    pub fn new(
+        input: &str,
+        schedule: Schedule,
+        scent_store: ScentStoreKind,
+        mut known_scents: Vec<RobotPosition>,
+        steps: Vec<Step>,
+    ) -> Self {
+        known_scents.sort_by_key(|scent| scent.to_string());
+        ReplayLog {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            input_hash: input_hash(input),
+            schedule,
+            scent_store,
+            known_scents,
+            steps,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Couldn't read replay log {}: {}", path.display(), e))?;
+        ReplayLog::from_str(&contents)
+            .map_err(|e| anyhow!("Bad replay log {}: {}", path.display(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Runs the mission again with the logged settings and scents.
+    pub fn rerun(&self, command: &Command) -> Vec<Step> {
+        fn run<S: ScentStore>(log: &ReplayLog, command: &Command, mut store: S) -> Vec<Step> {
+            for scent in &log.known_scents {
+                store.insert(scent.clone());
+            }
+            let mut scent_log = ScentLog::new(store, command.scent_decay);
+            run_mission(command, log.schedule, &mut scent_log).steps
+        }
+        match self.scent_store {
+            ScentStoreKind::Memory => run(self, command, HashSet::<RobotPosition>::new()),
+            ScentStoreKind::Bitset => run(self, command, BitsetScentStore::new(&command.bounds())),
+        }
+    }
+
+    /// Replays the mission the log was recorded from, returning the first
+    /// tick that no longer goes the way it did, if any.
+    pub fn verify(&self, input: &str) -> Result<ReplayOutcome, anyhow::Error> {
+        if input_hash(input) != self.input_hash {
+            return Err(anyhow!(
+                "The mission isn't the one the log was recorded from, its hash is {:016x} not {:016x}",
+                input_hash(input),
+                self.input_hash
+            ));
+        }
+        let command = parse_input_to_command(input).map_err(|e| anyhow!("{}", e))?;
+        if command.scent_decay.is_some() && !self.known_scents.is_empty() {
+            return Err(anyhow!(
+                "The mission has scent decay and the log doesn't record how old its known scents were"
+            ));
+        }
+        let replayed = self.rerun(&command);
+        Ok(ReplayOutcome {
+            recorded_with: Some(self.version.clone())
+                .filter(|version| version != env!("CARGO_PKG_VERSION")),
+            ticks: self.steps.len(),
+            divergence: first_divergence(&self.steps, &replayed),
+        })
+    }
+}
+
+pub fn first_divergence(logged: &[Step], replayed: &[Step]) -> Option<Divergence> {
+    (0..logged.len().max(replayed.len()))
+        .map(|index| Divergence {
+            logged: logged.get(index).cloned(),
+            replayed: replayed.get(index).cloned(),
+        })
+        .find(|divergence| divergence.logged != divergence.replayed)
+}
+
+impl FromStr for ReplayLog {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut version = None;
+        let mut input_hash = None;
+        let mut schedule = Schedule::default();
+        let mut scent_store = ScentStoreKind::default();
+        let mut known_scents = vec![];
+        let mut steps = vec![];
+
+        let lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "version" => version = Some(value.to_string()),
+                "input" => {
+                    input_hash = Some(
+                        u64::from_str_radix(value, 16)
+                            .map_err(|_| anyhow!("Bad input hash {}", value))?,
+                    )
+                }
+                "schedule" => schedule = Schedule::from_str(value)?,
+                "scent-store" => scent_store = ScentStoreKind::from_str(value)?,
+                "known" => known_scents.push(RobotPosition::from_str(value)?),
+                "tick" => steps.push(Step::from_str(line)?),
+                _ => return Err(anyhow!("Unknown line in replay log: {}", line)),
+            }
+        }
+
+        Ok(ReplayLog {
+            version: version.ok_or_else(|| anyhow!("Replay logs need a version line"))?,
+            input_hash: input_hash.ok_or_else(|| anyhow!("Replay logs need an input line"))?,
+            schedule,
+            scent_store,
+            known_scents,
+            steps,
+        })
+    }
+}
+
+impl fmt::Display for ReplayLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# martian-robots replay")?;
+        writeln!(f, "version {}", self.version)?;
+        writeln!(f, "input {:016x}", self.input_hash)?;
+        writeln!(f, "schedule {}", self.schedule)?;
+        writeln!(f, "scent-store {}", self.scent_store)?;
+        for scent in &self.known_scents {
+            writeln!(f, "known {}", scent)?;
+        }
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::ScentStoreKind;
+    use crate::parser::parse_input_to_command;
+    use crate::replay::{input_hash, ReplayLog};
+    use crate::robots::{RobotPosition, RobotStatus};
+    use crate::scents::ScentLog;
+    use crate::simulation::{run_mission, Schedule};
+    use rstest::rstest;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    const MISSION: &str = "5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n0 3 W\nLLFFFLFLFL";
+
+    fn record(input: &str, schedule: Schedule, known: &[&str]) -> ReplayLog {
+        let command = parse_input_to_command(input).unwrap();
+        let known: Vec<RobotPosition> = known
+            .iter()
+            .map(|scent| RobotPosition::from_str(scent).unwrap())
+            .collect();
+        let store: HashSet<RobotPosition> = known.iter().cloned().collect();
+        let mut scent_log = ScentLog::new(store, command.scent_decay);
+        let outcome = run_mission(&command, schedule, &mut scent_log);
+        ReplayLog::new(
+            input,
+            schedule,
+            ScentStoreKind::Memory,
+            known,
+            outcome.steps,
+        )
+    }
+
+    #[test]
+    fn test_log_records_every_tick() {
+        let log = record(MISSION, Schedule::Sequential, &[]);
+
+        assert_eq!(log.steps.len(), 8 + 8 + 10);
+        let lost = &log.steps[15];
+        assert_eq!(
+            lost.to_string(),
+            "tick 15 robot 1 F -> 3 3 N LOST scent 3 3 N"
+        );
+        assert_eq!(lost.status, RobotStatus::Lost);
+        assert!(
+            log.steps
+                .iter()
+                .filter(|step| !step.scents.is_empty())
+                .count()
+                == 1
+        );
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let log = record(MISSION, Schedule::RoundRobin, &["0 3 W"]);
+
+        assert_eq!(ReplayLog::from_str(&log.to_string()).unwrap(), log);
+    }
+
+    #[rstest]
+    #[case(Schedule::Sequential, &[])]
+    #[case(Schedule::RoundRobin, &[])]
+    #[case(Schedule::Sequential, &["3 3 N"])]
+    fn test_replay_matches(#[case] schedule: Schedule, #[case] known: &[&str]) {
+        let log = record(MISSION, schedule, known);
+
+        assert_eq!(log.verify(MISSION).unwrap().divergence, None);
+    }
+
+    #[test]
+    fn test_replay_reports_first_divergence() {
+        let mut log = record(MISSION, Schedule::Sequential, &[]);
+        log.steps[15].status = RobotStatus::Alive;
+        log.steps[15].scents.clear();
+
+        let divergence = log.verify(MISSION).unwrap().divergence.unwrap();
+
+        assert_eq!(
+            divergence.to_string(),
+            "logged:   tick 15 robot 1 F -> 3 3 N ALIVE\n\
+             replayed: tick 15 robot 1 F -> 3 3 N LOST scent 3 3 N"
+        );
+    }
+
+    #[test]
+    fn test_replay_reports_missing_ticks() {
+        let mut log = record(MISSION, Schedule::Sequential, &[]);
+        log.steps.truncate(20);
+
+        let divergence = log.verify(MISSION).unwrap().divergence.unwrap();
+
+        assert_eq!(divergence.logged, None);
+        assert_eq!(divergence.replayed.unwrap().tick, 20);
+    }
+
+    #[test]
+    fn test_replay_needs_the_same_mission() {
+        let log = record(MISSION, Schedule::Sequential, &[]);
+
+        assert!(log.verify("5 3\n1 1 E\nF").is_err());
+        assert_ne!(input_hash("5 3\n"), input_hash("5 3\r\n"));
+    }
+
+    #[test]
+    fn test_replay_reports_a_different_version() {
+        let mut log = record(MISSION, Schedule::Sequential, &[]);
+        log.version = "0.0.1".to_string();
+
+        let outcome = log.verify(MISSION).unwrap();
+
+        assert_eq!(outcome.recorded_with, Some("0.0.1".to_string()));
+        assert_eq!(outcome.divergence, None);
+        assert_eq!(
+            outcome.to_string(),
+            format!(
+                "Log recorded with version 0.0.1, replayed with {}\nAll 26 ticks match",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[rstest]
+    #[case(&[], true)]
+    #[case(&["3 3 N"], false)]
+    fn test_decay_replays_need_no_known_scents(#[case] known: &[&str], #[case] checked: bool) {
+        let mission = format!("decay robots 1\n{}", MISSION);
+        let log = record(&mission, Schedule::Sequential, known);
+
+        assert_eq!(log.verify(&mission).is_ok(), checked);
+    }
+}
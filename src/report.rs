@@ -0,0 +1,422 @@
+use crate::energy::EnergyCosts;
+use crate::json::Json;
+use crate::mission_instructions::{Command, Coordinates};
+use crate::plateau::{BoundaryPolicy, Obstacle, Region};
+use crate::robots::{Robot, RobotPosition};
+use crate::scents::{MissionClock, SavedBy, Scent, ScentLog, ScentStore};
+use crate::simulation::{Collision, MissionOutcome};
+
+/// The outcome of a mission: the plateau it ran on, every robot in its final
+/// state and the scents left behind, split into those still active at the end
+/// of the mission and those that had decayed, along with every robot a scent
+/// saved, every collision between robots and the path each robot took.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissionReport<'a> {
+    pub command: &'a Command,
+    pub robots: Vec<Robot>,
+    pub scents: Vec<Scent>,
+    pub expired_scents: Vec<Scent>,
+    pub saved_by: Vec<SavedBy>,
+    pub collisions: Vec<Collision>,
+    pub paths: Vec<Vec<Coordinates>>,
+    /// How many of the scents were laid during this mission.
+    pub scents_created: usize,
+}
+
+impl<'a> MissionReport<'a> {
+    pub fn new<S: ScentStore>(
+        command: &'a Command,
+        outcome: MissionOutcome,
+        scent_log: &ScentLog<S>,
+    ) -> Self {
+        MissionReport {
+            command,
+            robots: outcome.robots,
+            scents: scent_log.active(),
+            expired_scents: scent_log.expired(),
+            saved_by: scent_log.saved_by().to_vec(),
+            collisions: outcome.collisions,
+            paths: outcome.paths,
+            scents_created: scent_log.created(),
+        }
+    }
+
+    /// One line per robot, followed by the boundary policy when it isn't the
+    /// default `lost`.
+    pub fn text(&self) -> String {
+        let mut output = String::new();
+        for robot in &self.robots {
+            output.push_str(&format!("{}\n", robot.end_of_mission_report()));
+        }
+        if self.command.boundary != BoundaryPolicy::Lost {
+            output.push_str(&format!("boundary {}\n", self.command.boundary));
+        }
+        output
+    }
+
+    /// The robot's id, or its place in the mission when it hasn't got one.
+    pub fn robot_label(&self, index: usize) -> String {
+        self.robots
+            .get(index)
+            .map_or_else(|| index.to_string(), |robot| robot.label(index))
+    }
+
+    pub fn collision_message(&self, collision: &Collision) -> String {
+        format!(
+            "robot {} ran into robot {} at {} {} (tick {})",
+            self.robot_label(collision.robot),
+            self.robot_label(collision.other),
+            collision.at.x,
+            collision.at.y,
+            collision.tick
+        )
+    }
+
+    pub fn saved_by_message(&self, saved_by: &SavedBy) -> String {
+        format!(
+            "robot {} (line {}, command {}) saved by robot {}'s scent at {} (line {}, command {})",
+            self.robot_label(saved_by.saved.robot),
+            saved_by.saved.line,
+            saved_by.saved.command,
+            self.robot_label(saved_by.scent.laid.robot),
+            saved_by.scent.position,
+            saved_by.scent.laid.line,
+            saved_by.scent.laid.command
+        )
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut fields = vec![
+            (
+                "grid",
+                Json::object(vec![
+                    ("lower_left", coordinates_json(&self.command.lower_left)),
+                    ("upper_right", coordinates_json(&self.command.upper_right)),
+                ]),
+            ),
+            ("compass", Json::string(self.command.compass)),
+        ];
+        if let Some(region) = &self.command.region {
+            fields.push(("region", region_json(region)));
+        }
+        fields.push(("boundary", Json::string(self.command.boundary)));
+        fields.push(("scent_policy", Json::string(self.command.scent_policy)));
+        if let Some(decay) = &self.command.scent_decay {
+            fields.push(("scent_decay", Json::string(decay)));
+        }
+        fields.push((
+            "obstacles",
+            Json::Array(self.command.obstacles.iter().map(obstacle_json).collect()),
+        ));
+        fields.push((
+            "obstacle_policy",
+            Json::string(self.command.obstacle_policy),
+        ));
+        if let Some(budget) = self.command.energy {
+            fields.push(("energy", energy_json(budget, &self.command.energy_costs)));
+        }
+        fields.push((
+            "collision_policy",
+            Json::string(self.command.collision_policy),
+        ));
+        fields.extend([
+            (
+                "robots",
+                Json::Array(
+                    self.robots
+                        .iter()
+                        .enumerate()
+                        .map(|(index, robot)| robot_json(index, robot))
+                        .collect(),
+                ),
+            ),
+            (
+                "scents",
+                Json::Array(
+                    self.scents
+                        .iter()
+                        .map(|scent| scent_json(scent, &self.robots))
+                        .collect(),
+                ),
+            ),
+            (
+                "expired_scents",
+                Json::Array(
+                    self.expired_scents
+                        .iter()
+                        .map(|scent| scent_json(scent, &self.robots))
+                        .collect(),
+                ),
+            ),
+            (
+                "saved_by",
+                Json::Array(
+                    self.saved_by
+                        .iter()
+                        .map(|saved_by| saved_by_json(saved_by, &self.robots))
+                        .collect(),
+                ),
+            ),
+            (
+                "collisions",
+                Json::Array(
+                    self.collisions
+                        .iter()
+                        .map(|collision| collision_json(collision, &self.robots))
+                        .collect(),
+                ),
+            ),
+        ]);
+        Json::object(fields)
+    }
+}
+
+pub fn coordinates_json(coordinates: &Coordinates) -> Json {
+    Json::object(vec![
+        ("x", Json::from(coordinates.x)),
+        ("y", Json::from(coordinates.y)),
+    ])
+}
+
+pub fn position_json(position: &RobotPosition) -> Json {
+    Json::object(vec![
+        ("x", Json::from(position.coordinates.x)),
+        ("y", Json::from(position.coordinates.y)),
+        ("orientation", Json::string(&position.orientation)),
+    ])
+}
+
+/// The id of the robot at `index` under `key`, for robots that have one.
+fn robot_id_field(
+    key: &'static str,
+    index: usize,
+    robots: &[Robot],
+) -> Option<(&'static str, Json)> {
+    robots
+        .get(index)
+        .and_then(|robot| robot.id.as_ref())
+        .map(|id| (key, Json::string(id)))
+}
+
+fn clock_fields(clock: &MissionClock, robots: &[Robot]) -> Vec<(&'static str, Json)> {
+    let mut fields = vec![("robot", Json::from(clock.robot))];
+    fields.extend(robot_id_field("robot_id", clock.robot, robots));
+    fields.extend([
+        ("command", Json::from(clock.command)),
+        ("line", Json::from(clock.line)),
+        ("tick", Json::from(clock.tick)),
+    ]);
+    fields
+}
+
+fn scent_json(scent: &Scent, robots: &[Robot]) -> Json {
+    let mut fields = vec![
+        ("x", Json::from(scent.position.coordinates.x)),
+        ("y", Json::from(scent.position.coordinates.y)),
+        ("orientation", Json::string(&scent.position.orientation)),
+    ];
+    fields.extend(clock_fields(&scent.laid, robots));
+    Json::object(fields)
+}
+
+fn saved_by_json(saved_by: &SavedBy, robots: &[Robot]) -> Json {
+    let mut fields = clock_fields(&saved_by.saved, robots);
+    fields.push(("scent", scent_json(&saved_by.scent, robots)));
+    Json::object(fields)
+}
+
+fn collision_json(collision: &Collision, robots: &[Robot]) -> Json {
+    let mut fields = vec![
+        ("tick", Json::from(collision.tick)),
+        ("robot", Json::from(collision.robot)),
+    ];
+    fields.extend(robot_id_field("robot_id", collision.robot, robots));
+    fields.push(("other", Json::from(collision.other)));
+    fields.extend(robot_id_field("other_id", collision.other, robots));
+    fields.push(("at", coordinates_json(&collision.at)));
+    Json::object(fields)
+}
+
+fn obstacle_json(obstacle: &Obstacle) -> Json {
+    Json::object(vec![
+        ("lower_left", coordinates_json(&obstacle.lower_left)),
+        ("upper_right", coordinates_json(&obstacle.upper_right)),
+    ])
+}
+
+fn region_json(region: &Region) -> Json {
+    match region {
+        Region::Polygon(corners) => Json::object(vec![(
+            "polygon",
+            Json::Array(corners.iter().map(coordinates_json).collect()),
+        )]),
+        Region::Mask(rows) => Json::object(vec![(
+            "mask",
+            Json::Array(
+                rows.iter()
+                    .map(|row| {
+                        Json::String(
+                            row.iter()
+                                .map(|&ground| if ground { '#' } else { '.' })
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        )]),
+    }
+}
+
+fn robot_json(index: usize, robot: &Robot) -> Json {
+    let mut fields = vec![("index", Json::from(index))];
+    if let Some(id) = &robot.id {
+        fields.push(("id", Json::string(id)));
+        fields.push((
+            "metadata",
+            Json::object(
+                robot
+                    .metadata
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), Json::string(value)))
+                    .collect(),
+            ),
+        ));
+    }
+    if let Some(model) = &robot.model {
+        fields.push(("model", Json::string(&model.name)));
+    }
+    fields.push(("position", position_json(&robot.position)));
+    fields.push(("status", Json::string(&robot.robot_status)));
+    if let Some(energy) = robot.energy {
+        fields.push(("energy", Json::from(energy)));
+    }
+    Json::object(fields)
+}
+
+fn energy_json(budget: u32, costs: &EnergyCosts) -> Json {
+    Json::object(vec![
+        ("budget", Json::from(budget)),
+        (
+            "costs",
+            Json::object(vec![
+                ("forward", Json::from(costs.forward)),
+                ("turn", Json::from(costs.turn)),
+                ("blocked", Json::from(costs.blocked)),
+            ]),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::json::Json;
+    use crate::parser::parse_input_to_command;
+    use crate::report::MissionReport;
+    use crate::scents::ScentLog;
+    use crate::simulation::{run_mission, Schedule};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_json_report() {
+        let command = parse_input_to_command(
+            "obstacle 2 2\non-obstacle crash\n5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n1 2 E\nF",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(report.to_json())
+    }
+
+    #[test]
+    fn test_text_report() {
+        let command =
+            parse_input_to_command("boundary wrap\n5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL")
+                .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(report.text())
+    }
+
+    #[test]
+    fn test_json_report_key_order() {
+        let command = parse_input_to_command(
+            "decay ticks 3\nenergy 20\npolygon 0 0 5 0 5 3 0 3\n5 3\n1 1 E\nRF",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let Json::Object(fields) = MissionReport::new(&command, outcome, &scent_log).to_json()
+        else {
+            panic!("the report is an object");
+        };
+        let keys: Vec<&str> = fields.iter().map(|(key, _)| key.as_str()).collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                "grid",
+                "compass",
+                "region",
+                "boundary",
+                "scent_policy",
+                "scent_decay",
+                "obstacles",
+                "obstacle_policy",
+                "energy",
+                "collision_policy",
+                "robots",
+                "scents",
+                "expired_scents",
+                "saved_by",
+                "collisions",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_report_expired_scents() {
+        let command =
+            parse_input_to_command("decay ticks 1\n5 3\n3 3 N\nF\n\n0 0 S\nRRFF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        assert_eq!(report.scents, vec![]);
+        assert_eq!(report.expired_scents.len(), 1);
+        insta::assert_display_snapshot!(report.to_json())
+    }
+
+    #[test]
+    fn test_json_report_energy() {
+        let command =
+            parse_input_to_command("energy 3\ncost turn 2\n5 3\n1 1 E\nFRF\n\n2 2 N\nF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(report.to_json())
+    }
+
+    #[test]
+    fn test_json_report_named_robots() {
+        let command = parse_input_to_command(
+            "on-collision block\nmodel mk2\n5 3\n@rover-7 model=mk2\n3 3 N\nF\n\n@scout\n3 2 N\nFF\n\n3 2 N\nF",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let report = MissionReport::new(&command, outcome, &scent_log);
+
+        insta::assert_display_snapshot!(report.to_json())
+    }
+}
@@ -1,6 +1,10 @@
 use crate::mission_instructions::{Coordinates, Orientation, RobotCommands};
-use std::collections::HashSet;
+use crate::models::RobotModel;
+use crate::plateau::Plateau;
+use crate::scents::ScentStore;
+use anyhow::anyhow;
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct RobotPosition {
@@ -12,13 +16,34 @@ pub struct RobotPosition {
 pub enum RobotStatus {
     Alive,
     Lost,
+    Crashed,
+    Collided,
+    Depleted,
 }
 
 impl fmt::Display for RobotStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            RobotStatus::Alive => write!(f, "{:}", "ALIVE"),
-            RobotStatus::Lost => write!(f, "{:}", "LOST"),
+            RobotStatus::Alive => write!(f, "ALIVE"),
+            RobotStatus::Lost => write!(f, "LOST"),
+            RobotStatus::Crashed => write!(f, "CRASHED"),
+            RobotStatus::Collided => write!(f, "COLLIDED"),
+            RobotStatus::Depleted => write!(f, "DEPLETED"),
+        }
+    }
+}
+
+impl FromStr for RobotStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "ALIVE" => Ok(RobotStatus::Alive),
+            "LOST" => Ok(RobotStatus::Lost),
+            "CRASHED" => Ok(RobotStatus::Crashed),
+            "COLLIDED" => Ok(RobotStatus::Collided),
+            "DEPLETED" => Ok(RobotStatus::Depleted),
+            _ => Err(anyhow!("Unknown robot status {}", input)),
         }
     }
 }
@@ -32,23 +57,10 @@ impl RobotPosition {
     }
 
     pub fn move_forward(&self) -> Self {
-        let new_coordinates = match self.orientation {
-            Orientation::North => Coordinates {
-                x: self.coordinates.x,
-                y: self.coordinates.y + 1,
-            },
-            Orientation::South => Coordinates {
-                x: self.coordinates.x,
-                y: self.coordinates.y - 1,
-            },
-            Orientation::East => Coordinates {
-                x: self.coordinates.x + 1,
-                y: self.coordinates.y,
-            },
-            Orientation::West => Coordinates {
-                x: self.coordinates.x - 1,
-                y: self.coordinates.y,
-            },
+        let (dx, dy) = self.orientation.delta();
+        let new_coordinates = Coordinates {
+            x: self.coordinates.x + dx,
+            y: self.coordinates.y + dy,
         };
         RobotPosition {
             coordinates: new_coordinates,
@@ -56,15 +68,29 @@ impl RobotPosition {
         }
     }
 
-    pub fn check_scent(&self, scent_tracker: &mut HashSet<RobotPosition>) -> bool {
-        scent_tracker.contains(&self)
+    pub fn check_scent<S: ScentStore>(&self, scent_tracker: &S) -> bool {
+        scent_tracker.contains(self)
     }
 
-    pub fn add_scent(&self, scent_tracker: &mut HashSet<RobotPosition>) {
+    pub fn add_scent<S: ScentStore>(&self, scent_tracker: &mut S) {
         scent_tracker.insert(self.to_owned());
     }
 }
 
+impl FromStr for RobotPosition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (coordinates, orientation) = s
+            .rsplit_once(' ')
+            .ok_or_else(|| anyhow!("Robot position needs x y and a heading: {}", s))?;
+        Ok(RobotPosition {
+            coordinates: Coordinates::from_str(coordinates)?,
+            orientation: Orientation::from_str(orientation)?,
+        })
+    }
+}
+
 impl fmt::Display for RobotPosition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -80,6 +106,17 @@ pub struct Robot {
     pub position: RobotPosition,
     pub robot_commands: Vec<RobotCommands>,
     pub robot_status: RobotStatus,
+    /// The input line the robot's commands are on, counted from 1. Robots
+    /// that didn't come from an input have line 0.
+    pub line: usize,
+    /// Energy left, for missions with an energy budget.
+    pub energy: Option<u32>,
+    /// The name given by an `@id` line, if any.
+    pub id: Option<String>,
+    /// The `key=value` pairs that followed the id, in the order given.
+    pub metadata: Vec<(String, String)>,
+    /// The model picked with `model=<name>`, if any.
+    pub model: Option<RobotModel>,
 }
 
 impl Robot {
@@ -103,6 +140,11 @@ impl Robot {
             robot_commands,
             position: robot_position,
             robot_status: RobotStatus::Alive,
+            line: 0,
+            energy: None,
+            id: None,
+            metadata: vec![],
+            model: None,
         }
     }
 
@@ -114,6 +156,11 @@ impl Robot {
             },
             robot_commands: vec![],
             robot_status: RobotStatus::Alive,
+            line: 0,
+            energy: None,
+            id: None,
+            metadata: vec![],
+            model: None,
         }
     }
 
@@ -122,50 +169,140 @@ impl Robot {
             position: robot_position,
             robot_commands: self.robot_commands,
             robot_status: self.robot_status,
+            line: self.line,
+            energy: self.energy,
+            id: self.id,
+            metadata: self.metadata,
+            model: self.model,
+        }
+    }
+
+    pub fn set_line(self, line: usize) -> Self {
+        Robot {
+            position: self.position,
+            robot_commands: self.robot_commands,
+            robot_status: self.robot_status,
+            line,
+            energy: self.energy,
+            id: self.id,
+            metadata: self.metadata,
+            model: self.model,
+        }
+    }
+
+    pub fn set_energy(self, energy: Option<u32>) -> Self {
+        Robot {
+            position: self.position,
+            robot_commands: self.robot_commands,
+            robot_status: self.robot_status,
+            line: self.line,
+            energy,
+            id: self.id,
+            metadata: self.metadata,
+            model: self.model,
+        }
+    }
+
+    pub fn set_identity(self, id: String, metadata: Vec<(String, String)>) -> Self {
+        Robot {
+            position: self.position,
+            robot_commands: self.robot_commands,
+            robot_status: self.robot_status,
+            line: self.line,
+            energy: self.energy,
+            id: Some(id),
+            metadata,
+            model: self.model,
+        }
+    }
+
+    pub fn set_model(self, model: Option<RobotModel>) -> Self {
+        Robot {
+            position: self.position,
+            robot_commands: self.robot_commands,
+            robot_status: self.robot_status,
+            line: self.line,
+            energy: self.energy,
+            id: self.id,
+            metadata: self.metadata,
+            model,
         }
     }
 
+    /// The value given for `key` in the robot's metadata.
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// How the robot is referred to in messages: its id, or failing that its
+    /// place in the mission.
+    pub fn label(&self, index: usize) -> String {
+        self.id.clone().unwrap_or_else(|| index.to_string())
+    }
+
     pub fn update_commands(self, robot_commands: Vec<RobotCommands>) -> Self {
         Robot {
             position: self.position,
             robot_commands,
             robot_status: self.robot_status,
+            line: self.line,
+            energy: self.energy,
+            id: self.id,
+            metadata: self.metadata,
+            model: self.model,
         }
     }
 
-    pub fn process_robot_command(
+    pub fn process_robot_command<S: ScentStore>(
         mut self,
-        coordinate_limit: &Coordinates,
-        scent_tracker: &mut HashSet<RobotPosition>,
+        plateau: &Plateau,
+        scent_tracker: &mut S,
     ) -> Self {
         let command = self.robot_commands.first().unwrap();
-        let (new_position, robot_status) =
-            command.process(self.position, coordinate_limit, scent_tracker);
+        let (new_position, robot_status) = command.process(self.position, plateau, scent_tracker);
         self.robot_commands.remove(0);
 
         Robot {
             position: new_position,
             robot_commands: self.robot_commands,
             robot_status,
+            line: self.line,
+            energy: self.energy,
+            id: self.id,
+            metadata: self.metadata,
+            model: self.model,
         }
     }
 
-    pub fn process_all_commands(
+    /// Still alive and with commands left to carry out.
+    pub fn is_active(&self) -> bool {
+        !self.robot_commands.is_empty() && self.robot_status == RobotStatus::Alive
+    }
+
+    #[cfg(test)]
+    pub fn process_all_commands<S: ScentStore>(
         mut self,
-        coordinate_limit: &Coordinates,
-        scent_tracker: &mut HashSet<RobotPosition>,
+        plateau: &Plateau,
+        scent_tracker: &mut S,
     ) -> Self {
-        while !self.robot_commands.is_empty() && self.robot_status == RobotStatus::Alive {
-            self = self.process_robot_command(coordinate_limit, scent_tracker);
+        while self.is_active() {
+            self = self.process_robot_command(plateau, scent_tracker);
         }
         self
     }
 
-    pub fn end_of_mission_report(&self) {
-        if self.robot_status == RobotStatus::Lost {
-            println!("{} {}", self.position, self.robot_status)
+    pub fn end_of_mission_report(&self) -> String {
+        let id = self
+            .id
+            .as_ref()
+            .map_or_else(String::new, |id| format!("{}: ", id));
+        if self.robot_status != RobotStatus::Alive {
+            format!("{}{} {}", id, self.position, self.robot_status)
         } else {
-            println!("{}", self.position)
+            format!("{}{}", id, self.position)
         }
     }
 }
@@ -173,6 +310,7 @@ impl Robot {
 #[cfg(test)]
 mod test {
     use crate::mission_instructions::{Coordinates, Orientation, RobotCommands};
+    use crate::plateau::Plateau;
     use crate::robots::{Robot, RobotPosition};
     use rstest::*;
     use std::collections::HashSet;
@@ -182,6 +320,8 @@ mod test {
     #[case(Orientation::South, "south")]
     #[case(Orientation::West, "west")]
     #[case(Orientation::East, "east")]
+    #[case(Orientation::NorthEast, "north_east")]
+    #[case(Orientation::SouthWest, "south_west")]
     fn test_from_str_for_coords(#[case] orientation: Orientation, #[case] snapshot_suffix: &str) {
         let mut settings = insta::Settings::new();
         settings.set_snapshot_suffix(snapshot_suffix);
@@ -207,7 +347,7 @@ mod test {
 
         let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
         let robot = Robot::new();
-        let coordinates = Coordinates { x: 5, y: 3 };
+        let coordinates = Plateau::new(Coordinates { x: 5, y: 3 }.into());
         let robot_with_commands = robot.update_commands(vec![command]);
         let processed_robot =
             robot_with_commands.process_robot_command(&coordinates, &mut scent_tracker);
@@ -220,7 +360,7 @@ mod test {
     #[test]
     fn test_process_all_commands() {
         let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
-        let coordinates = Coordinates { x: 5, y: 3 };
+        let coordinates = Plateau::new(Coordinates { x: 5, y: 3 }.into());
         let new_basic_robot = Robot::new_basic_robot();
         let processed_robot =
             new_basic_robot.process_all_commands(&coordinates, &mut scent_tracker);
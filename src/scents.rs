@@ -0,0 +1,1171 @@
+use crate::mission_instructions::{Coordinates, GridBounds, Orientation};
+use crate::plateau::Plateau;
+use crate::robots::RobotPosition;
+use anyhow::anyhow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Somewhere to keep the scents left by lost robots. The simulation only needs
+/// to ask whether a position is scented and to lay new scents, so anything
+/// that can do that can stand in for the plain `HashSet`.
+pub trait ScentStore {
+    fn contains(&self, position: &RobotPosition) -> bool;
+
+    fn insert(&mut self, position: RobotPosition);
+
+    /// Takes back a scent, for stepping a mission backwards.
+    fn remove(&mut self, position: &RobotPosition);
+
+    /// Every scent currently held, in no particular order.
+    fn scents(&self) -> Vec<RobotPosition>;
+
+    /// Told when the scent at `position` stops a robot driving off the
+    /// plateau. Plain stores have nothing to do with it.
+    fn saved(&mut self, _position: &RobotPosition) {}
+}
+
+impl ScentStore for HashSet<RobotPosition> {
+    fn contains(&self, position: &RobotPosition) -> bool {
+        HashSet::contains(self, position)
+    }
+
+    fn insert(&mut self, position: RobotPosition) {
+        HashSet::insert(self, position);
+    }
+
+    fn remove(&mut self, position: &RobotPosition) {
+        HashSet::remove(self, position);
+    }
+
+    fn scents(&self) -> Vec<RobotPosition> {
+        self.iter().cloned().collect()
+    }
+}
+
+/// How a robot without a scent sensor sees a store: it never notices a scent
+/// but still leaves one behind when it is lost.
+pub struct ScentBlind<'a, S>(pub &'a mut S);
+
+impl<'a, S: ScentStore> ScentStore for ScentBlind<'a, S> {
+    fn contains(&self, _position: &RobotPosition) -> bool {
+        false
+    }
+
+    fn insert(&mut self, position: RobotPosition) {
+        self.0.insert(position)
+    }
+
+    fn remove(&mut self, position: &RobotPosition) {
+        self.0.remove(position)
+    }
+
+    fn scents(&self) -> Vec<RobotPosition> {
+        self.0.scents()
+    }
+}
+
+/// Lets a move be tried against the known scents without laying new ones
+/// when it would lose the robot.
+pub struct KnownScents<'a, S>(pub &'a S);
+
+impl<'a, S: ScentStore> ScentStore for KnownScents<'a, S> {
+    fn contains(&self, position: &RobotPosition) -> bool {
+        self.0.contains(position)
+    }
+
+    fn insert(&mut self, _position: RobotPosition) {}
+
+    fn remove(&mut self, _position: &RobotPosition) {}
+
+    fn scents(&self) -> Vec<RobotPosition> {
+        self.0.scents()
+    }
+}
+
+/// Every heading on the 8-way compass, clockwise from north.
+pub const HEADINGS: [Orientation; 8] = [
+    Orientation::North,
+    Orientation::NorthEast,
+    Orientation::East,
+    Orientation::SouthEast,
+    Orientation::South,
+    Orientation::SouthWest,
+    Orientation::West,
+    Orientation::NorthWest,
+];
+
+/// How a scent left by a lost robot protects the robots that come after it.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ScentPolicy {
+    /// Only a robot on the same square facing the same way is protected.
+    #[default]
+    Exact,
+    /// Any move off the plateau from a scented square is ignored, whichever
+    /// way the robot faces.
+    Square,
+    /// Scents mark the side of the square that was crossed, so a diagonal
+    /// move over an edge is stopped by a scent left by a straight move over
+    /// the same edge, and the other way round.
+    Edge,
+}
+
+impl FromStr for ScentPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "exact" => Ok(ScentPolicy::Exact),
+            "square" => Ok(ScentPolicy::Square),
+            "edge" => Ok(ScentPolicy::Edge),
+            _ => Err(anyhow!(
+                "Scent policy must be exact, square or edge, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ScentPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScentPolicy::Exact => write!(f, "exact"),
+            ScentPolicy::Square => write!(f, "square"),
+            ScentPolicy::Edge => write!(f, "edge"),
+        }
+    }
+}
+
+impl ScentPolicy {
+    /// The scent, if any, that means a forward move from `position` should be
+    /// ignored.
+    pub fn protecting_scent<S: ScentStore>(
+        &self,
+        position: &RobotPosition,
+        plateau: &Plateau,
+        scent_tracker: &S,
+    ) -> Option<RobotPosition> {
+        match self {
+            ScentPolicy::Exact => Some(position.clone()).filter(|p| p.check_scent(scent_tracker)),
+            ScentPolicy::Square if leaves_plateau(position, plateau) => HEADINGS
+                .iter()
+                .map(|heading| position.clone().update_orientation(heading.clone()))
+                .find(|scent| scent.check_scent(scent_tracker)),
+            ScentPolicy::Edge if leaves_plateau(position, plateau) => exit_edges(position, plateau)
+                .into_iter()
+                .find(|edge| edge.check_scent(scent_tracker)),
+            ScentPolicy::Square | ScentPolicy::Edge => None,
+        }
+    }
+
+    /// Lays the scents for a robot lost moving forward from `position`.
+    pub fn record<S: ScentStore>(
+        &self,
+        position: &RobotPosition,
+        plateau: &Plateau,
+        scent_tracker: &mut S,
+    ) {
+        match self {
+            ScentPolicy::Exact | ScentPolicy::Square => position.add_scent(scent_tracker),
+            ScentPolicy::Edge => {
+                for edge in exit_edges(position, plateau) {
+                    edge.add_scent(scent_tracker)
+                }
+            }
+        }
+    }
+}
+
+fn leaves_plateau(position: &RobotPosition, plateau: &Plateau) -> bool {
+    plateau.off_plateau(&position.move_forward().coordinates)
+}
+
+/// The sides of the square a forward move goes over, as the square plus the
+/// straight heading pointing at that side. A diagonal move goes over whichever
+/// of its two sides lead off the plateau, or over both when it cuts a corner.
+fn exit_edges(position: &RobotPosition, plateau: &Plateau) -> Vec<RobotPosition> {
+    let (dx, dy) = position.orientation.delta();
+    let mut sides = vec![];
+    if dx != 0 {
+        sides.push(if dx > 0 {
+            Orientation::East
+        } else {
+            Orientation::West
+        });
+    }
+    if dy != 0 {
+        sides.push(if dy > 0 {
+            Orientation::North
+        } else {
+            Orientation::South
+        });
+    }
+
+    let edges: Vec<RobotPosition> = sides
+        .into_iter()
+        .map(|side| position.clone().update_orientation(side))
+        .collect();
+    let crossed: Vec<RobotPosition> = edges
+        .iter()
+        .filter(|edge| leaves_plateau(edge, plateau))
+        .cloned()
+        .collect();
+
+    if crossed.is_empty() {
+        edges
+    } else {
+        crossed
+    }
+}
+
+/// One bit per square and heading inside the grid. Grids are at most 51x51 so
+/// this stays small and lookups never hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitsetScentStore {
+    bounds: GridBounds,
+    bits: Vec<u64>,
+}
+
+impl BitsetScentStore {
+    pub fn new(bounds: &GridBounds) -> Self {
+        let size = (bounds.width() * bounds.height()) as usize * HEADINGS.len();
+        BitsetScentStore {
+            bounds: bounds.clone(),
+            bits: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    fn index(&self, position: &RobotPosition) -> Option<usize> {
+        if position.coordinates.fallen_off_grid(&self.bounds) {
+            return None;
+        }
+        let heading = HEADINGS
+            .iter()
+            .position(|heading| *heading == position.orientation)?;
+        let square = (position.coordinates.y - self.bounds.lower_left.y) * self.bounds.width()
+            + (position.coordinates.x - self.bounds.lower_left.x);
+        Some(square as usize * HEADINGS.len() + heading)
+    }
+
+    fn position(&self, index: usize) -> RobotPosition {
+        let square = (index / HEADINGS.len()) as i32;
+        RobotPosition {
+            coordinates: Coordinates {
+                x: self.bounds.lower_left.x + square % self.bounds.width(),
+                y: self.bounds.lower_left.y + square / self.bounds.width(),
+            },
+            orientation: HEADINGS[index % HEADINGS.len()].clone(),
+        }
+    }
+}
+
+impl ScentStore for BitsetScentStore {
+    fn contains(&self, position: &RobotPosition) -> bool {
+        match self.index(position) {
+            Some(index) => self.bits[index / 64] & (1 << (index % 64)) != 0,
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, position: RobotPosition) {
+        // robots are only ever lost from a square on the grid, so there is
+        // nothing to keep for anything outside it
+        if let Some(index) = self.index(&position) {
+            self.bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    fn remove(&mut self, position: &RobotPosition) {
+        if let Some(index) = self.index(position) {
+            self.bits[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    fn scents(&self) -> Vec<RobotPosition> {
+        (0..self.bits.len() * 64)
+            .filter(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+            .map(|index| self.position(index))
+            .collect()
+    }
+}
+
+/// The scent file kept between runs. It records the grid the scents were laid
+/// on so they are never applied to a different plateau:
+///
+/// ```text
+/// # martian-robots scents
+/// grid 0 0 5 3
+/// 3 3 N
+/// 0 3 W
+/// ```
+///
+/// `grid` takes the lower left and upper right corners, every other line is a
+/// scented `x y heading`. Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScentFile {
+    pub bounds: GridBounds,
+    pub scents: Vec<RobotPosition>,
+}
+
+impl ScentFile {
+    pub fn new(bounds: GridBounds) -> Self {
+        ScentFile {
+            bounds,
+            scents: vec![],
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Couldn't read scent file {}: {}", path.display(), e))?;
+        ScentFile::from_str(&contents)
+            .map_err(|e| anyhow!("Bad scent file {}: {}", path.display(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Adds scents that aren't already in the file, keeping it sorted.
+    pub fn merge(&mut self, scents: Vec<RobotPosition>) -> Result<(), anyhow::Error> {
+        for scent in scents {
+            if scent.coordinates.fallen_off_grid(&self.bounds) {
+                return Err(anyhow!(
+                    "Scent {} is outside of the grid {}",
+                    scent,
+                    self.bounds
+                ));
+            }
+            if !self.scents.contains(&scent) {
+                self.scents.push(scent);
+            }
+        }
+        self.scents.sort_by_key(|scent| scent.to_string());
+        Ok(())
+    }
+}
+
+impl FromStr for ScentFile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let bounds = match lines.next().and_then(|line| line.strip_prefix("grid ")) {
+            Some(bounds) => GridBounds::from_str(bounds)?,
+            None => return Err(anyhow!("Scent files have to start with a grid line")),
+        };
+
+        let scents = lines
+            .map(RobotPosition::from_str)
+            .collect::<Result<Vec<RobotPosition>, anyhow::Error>>()?;
+
+        let mut file = ScentFile::new(bounds);
+        file.merge(scents)?;
+        Ok(file)
+    }
+}
+
+impl fmt::Display for ScentFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# martian-robots scents")?;
+        writeln!(f, "grid {}", self.bounds)?;
+        for scent in &self.scents {
+            writeln!(f, "{}", scent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps another store and keeps its scents in a `ScentFile` between runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistentScentStore<S> {
+    inner: S,
+    path: PathBuf,
+    bounds: GridBounds,
+}
+
+impl<S: ScentStore> PersistentScentStore<S> {
+    /// Loads any scents already in the file into `inner`. A missing file is
+    /// just an empty store, but a file recorded for another grid is refused.
+    pub fn open<P: Into<PathBuf>>(
+        path: P,
+        bounds: &GridBounds,
+        mut inner: S,
+    ) -> Result<Self, anyhow::Error> {
+        let path = path.into();
+        if path.exists() {
+            let file = ScentFile::read(&path)?;
+            if file.bounds != *bounds {
+                return Err(anyhow!(
+                    "Scent file {} was recorded on grid {} but this mission uses {}",
+                    path.display(),
+                    file.bounds,
+                    bounds
+                ));
+            }
+            for scent in file.scents {
+                inner.insert(scent);
+            }
+        }
+        Ok(PersistentScentStore {
+            inner,
+            path,
+            bounds: bounds.clone(),
+        })
+    }
+
+    /// Replaces the file's scents with `scents`.
+    pub fn save(&self, scents: Vec<RobotPosition>) -> Result<(), anyhow::Error> {
+        let mut file = ScentFile::new(self.bounds.clone());
+        file.merge(scents)?;
+        file.write(&self.path)
+    }
+}
+
+impl<S: ScentStore> ScentStore for PersistentScentStore<S> {
+    fn contains(&self, position: &RobotPosition) -> bool {
+        self.inner.contains(position)
+    }
+
+    fn insert(&mut self, position: RobotPosition) {
+        self.inner.insert(position)
+    }
+
+    fn remove(&mut self, position: &RobotPosition) {
+        self.inner.remove(position)
+    }
+
+    fn scents(&self) -> Vec<RobotPosition> {
+        self.inner.scents()
+    }
+
+    fn saved(&mut self, position: &RobotPosition) {
+        self.inner.saved(position)
+    }
+}
+
+/// Where the mission has got to: which robot is moving, which of its commands
+/// it is carrying out and the input line those commands are on, and how many
+/// ticks have passed since the first robot was dropped.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct MissionClock {
+    pub robot: usize,
+    pub command: usize,
+    pub line: usize,
+    pub tick: usize,
+}
+
+/// A scent along with when, and by which robot, it was laid.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Scent {
+    pub position: RobotPosition,
+    pub laid: MissionClock,
+}
+
+/// A robot that ignored a forward move because of a scent, and the scent
+/// that saved it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SavedBy {
+    pub saved: MissionClock,
+    pub scent: Scent,
+}
+
+/// How long a scent lasts before it stops protecting anyone.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ScentDecay {
+    /// Honoured by the next N robots after the one that was lost.
+    Robots(usize),
+    /// Honoured for N ticks after it was laid.
+    Ticks(usize),
+}
+
+impl FromStr for ScentDecay {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (unit, amount) = input
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Decay needs a unit and an amount, e.g. robots 3"))?;
+        let amount = amount.trim().parse::<usize>()?;
+        match unit {
+            "robots" => Ok(ScentDecay::Robots(amount)),
+            "ticks" => Ok(ScentDecay::Ticks(amount)),
+            _ => Err(anyhow!("Decay must be in robots or ticks, got {}", unit)),
+        }
+    }
+}
+
+impl fmt::Display for ScentDecay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScentDecay::Robots(amount) => write!(f, "robots {}", amount),
+            ScentDecay::Ticks(amount) => write!(f, "ticks {}", amount),
+        }
+    }
+}
+
+/// A scent being laid, with what the log knew about that position before so
+/// it can be taken back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaidScent {
+    pub position: RobotPosition,
+    was_stored: bool,
+    laid_before: Option<MissionClock>,
+}
+
+/// Where a `ScentLog` was before a tick, see `ScentLog::mark`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScentMark {
+    clock: MissionClock,
+    saved_by: usize,
+}
+
+/// Sits in front of any store and remembers when each scent was laid, so
+/// that decayed scents stop protecting robots and a scent laid in one tick is
+/// only picked up from the next. Scents that were already in the store, e.g.
+/// loaded from a scent file, count as laid before the mission started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScentLog<S> {
+    inner: S,
+    laid: HashMap<RobotPosition, MissionClock>,
+    saved_by: Vec<SavedBy>,
+    clock: MissionClock,
+    decay: Option<ScentDecay>,
+    /// Scents laid since the last `mark`, only kept once one has been made.
+    journal: Option<Vec<LaidScent>>,
+}
+
+impl<S: ScentStore> ScentLog<S> {
+    pub fn new(inner: S, decay: Option<ScentDecay>) -> Self {
+        ScentLog {
+            inner,
+            laid: HashMap::new(),
+            saved_by: vec![],
+            clock: MissionClock::default(),
+            decay,
+            journal: None,
+        }
+    }
+
+    /// Starts keeping track of the scents laid from here on, so they can be
+    /// taken back with `undo`.
+    pub fn mark(&mut self) -> ScentMark {
+        self.journal = Some(vec![]);
+        ScentMark {
+            clock: self.clock,
+            saved_by: self.saved_by.len(),
+        }
+    }
+
+    /// The scents laid since the last `mark`, oldest first.
+    pub fn laid_since_mark(&mut self) -> Vec<LaidScent> {
+        self.journal.take().unwrap_or_default()
+    }
+
+    /// Puts the log back the way it was at `mark`, given the scents laid
+    /// since.
+    pub fn undo(&mut self, mark: &ScentMark, laid: &[LaidScent]) {
+        for scent in laid.iter().rev() {
+            match scent.laid_before {
+                Some(clock) => self.laid.insert(scent.position.clone(), clock),
+                None => self.laid.remove(&scent.position),
+            };
+            if !scent.was_stored {
+                self.inner.remove(&scent.position);
+            }
+        }
+        self.saved_by.truncate(mark.saved_by);
+        self.clock = mark.clock;
+    }
+
+    pub fn set_clock(&mut self, clock: MissionClock) {
+        self.clock = clock;
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// How many scents were laid during the mission, leaving out any the
+    /// store already held.
+    pub fn created(&self) -> usize {
+        self.laid.len()
+    }
+
+    /// The scents laid since the clock was last set, in a fixed order.
+    pub fn laid_now(&self) -> Vec<RobotPosition> {
+        let mut scents: Vec<RobotPosition> = self
+            .laid
+            .iter()
+            .filter(|(_, laid)| **laid == self.clock)
+            .map(|(position, _)| position.clone())
+            .collect();
+        scents.sort_by_key(|position| position.to_string());
+        scents
+    }
+
+    /// Every time a scent saved a robot, in the order it happened.
+    pub fn saved_by(&self) -> &[SavedBy] {
+        &self.saved_by
+    }
+
+    fn laid(&self, position: &RobotPosition) -> MissionClock {
+        self.laid.get(position).copied().unwrap_or_default()
+    }
+
+    fn is_expired(&self, laid: &MissionClock) -> bool {
+        match self.decay {
+            Some(ScentDecay::Robots(amount)) => self.clock.robot > laid.robot + amount,
+            Some(ScentDecay::Ticks(amount)) => self.clock.tick > laid.tick + amount,
+            None => false,
+        }
+    }
+
+    fn all_scents(&self) -> Vec<Scent> {
+        let mut scents: Vec<Scent> = self
+            .inner
+            .scents()
+            .into_iter()
+            .map(|position| Scent {
+                laid: self.laid(&position),
+                position,
+            })
+            .collect();
+        scents.sort_by_key(|scent| {
+            (
+                scent.position.coordinates.x,
+                scent.position.coordinates.y,
+                scent.position.orientation.to_string(),
+            )
+        });
+        scents
+    }
+
+    /// Scents still protecting robots at the current clock.
+    pub fn active(&self) -> Vec<Scent> {
+        self.all_scents()
+            .into_iter()
+            .filter(|scent| !self.is_expired(&scent.laid))
+            .collect()
+    }
+
+    pub fn expired(&self) -> Vec<Scent> {
+        self.all_scents()
+            .into_iter()
+            .filter(|scent| self.is_expired(&scent.laid))
+            .collect()
+    }
+}
+
+impl<S: ScentStore> ScentLog<PersistentScentStore<S>> {
+    /// Saves the scents still active to the scent file. The file doesn't
+    /// record when scents were laid, so ones that have decayed are left out
+    /// rather than coming back fresh on the next run.
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        self.inner.save(self.scents())
+    }
+}
+
+impl<S: ScentStore> ScentStore for ScentLog<S> {
+    fn contains(&self, position: &RobotPosition) -> bool {
+        let laid_this_tick = self
+            .laid
+            .get(position)
+            .is_some_and(|laid| laid.tick >= self.clock.tick);
+        self.inner.contains(position) && !laid_this_tick && !self.is_expired(&self.laid(position))
+    }
+
+    fn insert(&mut self, position: RobotPosition) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push(LaidScent {
+                position: position.clone(),
+                was_stored: self.inner.contains(&position),
+                laid_before: self.laid.get(&position).copied(),
+            });
+        }
+        // losing a robot on a decayed scent lays it again from now
+        self.laid.insert(position.clone(), self.clock);
+        self.inner.insert(position);
+    }
+
+    fn remove(&mut self, position: &RobotPosition) {
+        self.laid.remove(position);
+        self.inner.remove(position);
+    }
+
+    fn scents(&self) -> Vec<RobotPosition> {
+        self.active()
+            .into_iter()
+            .map(|scent| scent.position)
+            .collect()
+    }
+
+    fn saved(&mut self, position: &RobotPosition) {
+        self.saved_by.push(SavedBy {
+            saved: self.clock,
+            scent: Scent {
+                position: position.clone(),
+                laid: self.laid(position),
+            },
+        });
+        self.inner.saved(position)
+    }
+}
+
+/// What the `scents` subcommand can do with stored scent files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScentsAction {
+    List(PathBuf),
+    /// Merge every source file into the target, creating it if needed.
+    Merge {
+        target: PathBuf,
+        sources: Vec<PathBuf>,
+    },
+    Clear(PathBuf),
+}
+
+pub fn run_scents_action(action: &ScentsAction) -> Result<String, anyhow::Error> {
+    match action {
+        ScentsAction::List(path) => Ok(ScentFile::read(path)?.to_string()),
+        ScentsAction::Merge { target, sources } => {
+            let mut merged: Option<ScentFile> = if target.exists() {
+                Some(ScentFile::read(target)?)
+            } else {
+                None
+            };
+            for source in sources {
+                let file = ScentFile::read(source)?;
+                match merged.as_mut() {
+                    Some(merged) if merged.bounds != file.bounds => {
+                        return Err(anyhow!(
+                            "Can't merge {} recorded on grid {} into grid {}",
+                            source.display(),
+                            file.bounds,
+                            merged.bounds
+                        ))
+                    }
+                    Some(merged) => merged.merge(file.scents)?,
+                    None => merged = Some(file),
+                }
+            }
+            let merged = merged.ok_or_else(|| anyhow!("Nothing to merge"))?;
+            merged.write(target)?;
+            Ok(format!(
+                "{} scents in {}\n",
+                merged.scents.len(),
+                target.display()
+            ))
+        }
+        ScentsAction::Clear(path) => {
+            let file = ScentFile::read(path)?;
+            ScentFile::new(file.bounds).write(path)?;
+            Ok(format!(
+                "Cleared {} scents from {}\n",
+                file.scents.len(),
+                path.display()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mission_instructions::{Coordinates, GridBounds, Orientation, RobotCommands};
+    use crate::parser::parse_input_to_command;
+    use crate::plateau::Plateau;
+    use crate::robots::{Robot, RobotPosition, RobotStatus};
+    use crate::scents::{
+        run_scents_action, BitsetScentStore, MissionClock, PersistentScentStore, SavedBy, Scent,
+        ScentDecay, ScentFile, ScentLog, ScentPolicy, ScentStore, ScentsAction,
+    };
+    use crate::simulation::{run_mission, Schedule};
+    use rstest::*;
+    use std::cell::Cell;
+    use std::collections::HashSet;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    /// Counts how often the simulation asks about scents.
+    #[derive(Default)]
+    struct CountingScentStore {
+        inner: HashSet<RobotPosition>,
+        lookups: Cell<usize>,
+        inserts: usize,
+    }
+
+    impl ScentStore for CountingScentStore {
+        fn contains(&self, position: &RobotPosition) -> bool {
+            self.lookups.set(self.lookups.get() + 1);
+            self.inner.contains(position)
+        }
+
+        fn insert(&mut self, position: RobotPosition) {
+            self.inserts += 1;
+            self.inner.insert(position);
+        }
+
+        fn remove(&mut self, position: &RobotPosition) {
+            self.inner.remove(position);
+        }
+
+        fn scents(&self) -> Vec<RobotPosition> {
+            self.inner.scents()
+        }
+    }
+
+    fn position(input: &str) -> RobotPosition {
+        RobotPosition::from_str(input).unwrap()
+    }
+
+    #[test]
+    fn test_bitset_store_round_trip() {
+        let bounds = GridBounds::from_str("-2 -1 3 2").unwrap();
+        let mut store = BitsetScentStore::new(&bounds);
+
+        store.insert(position("-2 -1 SW"));
+        store.insert(position("3 2 NE"));
+        store.insert(position("0 0 N"));
+        store.insert(position("9 9 N"));
+
+        assert!(store.contains(&position("-2 -1 SW")));
+        assert!(store.contains(&position("3 2 NE")));
+        assert!(!store.contains(&position("3 2 N")));
+        assert!(!store.contains(&position("9 9 N")));
+        assert_eq!(
+            store.scents(),
+            vec![position("-2 -1 SW"), position("0 0 N"), position("3 2 NE")]
+        );
+    }
+
+    #[test]
+    fn test_pre_seeded_store_saves_robot() {
+        let mut store = BitsetScentStore::new(&Coordinates { x: 5, y: 3 }.into());
+        store.insert(position("3 3 N"));
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into());
+
+        let robot = Robot::new()
+            .set_start_position(position("3 2 N"))
+            .update_commands(vec![RobotCommands::Forward; 3]);
+        let robot = robot.process_all_commands(&plateau, &mut store);
+
+        assert_eq!(robot.position, position("3 3 N"));
+        assert_eq!(robot.robot_status, RobotStatus::Alive);
+    }
+
+    #[test]
+    fn test_instrumented_store() {
+        let mut store = CountingScentStore::default();
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into());
+
+        let robot = Robot::new()
+            .set_start_position(position("0 3 W"))
+            .update_commands(vec![RobotCommands::Forward; 2]);
+        let robot = robot.process_all_commands(&plateau, &mut store);
+
+        assert_eq!(robot.position.orientation, Orientation::West);
+        assert_eq!(store.lookups.get(), 1);
+        assert_eq!(store.inserts, 1);
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("{}-{}.txt", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_persistent_store() {
+        let path = temp_path("scents");
+        let bounds = GridBounds::from_str("5 3").unwrap();
+
+        let mut store = PersistentScentStore::open(&path, &bounds, HashSet::new()).unwrap();
+        store.insert(position("3 3 N"));
+        store.insert(position("0 3 W"));
+        store.save(store.scents()).unwrap();
+
+        let store = PersistentScentStore::open(&path, &bounds, HashSet::new()).unwrap();
+        assert!(store.contains(&position("3 3 N")));
+        assert!(store.contains(&position("0 3 W")));
+        insta::assert_display_snapshot!(fs::read_to_string(&path).unwrap());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    // the scent decays before the second robot finishes, so it isn't saved
+    #[case("decay ticks 1", vec![])]
+    #[case("decay ticks 10", vec!["3 3 N"])]
+    #[case("decay robots 0", vec![])]
+    #[case("decay robots 1", vec!["3 3 N"])]
+    fn test_persistent_store_keeps_decay_across_runs(
+        #[case] decay: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        let path = temp_path(&format!("scents-{}", decay.replace(' ', "-")));
+        let command =
+            parse_input_to_command(&format!("{}\n5 3\n3 3 N\nF\n\n0 0 S\nRRFF", decay)).unwrap();
+
+        let store = PersistentScentStore::open(&path, &command.bounds(), HashSet::new()).unwrap();
+        let mut scent_log = ScentLog::new(store, command.scent_decay);
+        run_mission(&command, Schedule::Sequential, &mut scent_log);
+        scent_log.save().unwrap();
+
+        let store = PersistentScentStore::open(&path, &command.bounds(), HashSet::new()).unwrap();
+        let mut saved: Vec<String> = store
+            .scents()
+            .iter()
+            .map(|scent| scent.to_string())
+            .collect();
+        saved.sort();
+        assert_eq!(saved, expected);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_store_rejects_other_grid() {
+        let path = temp_path("scents-other-grid");
+        fs::write(&path, "grid 0 0 5 3\n3 3 N\n").unwrap();
+
+        let bounds = GridBounds::from_str("7 7").unwrap();
+        assert!(PersistentScentStore::open(&path, &bounds, HashSet::new()).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    #[case("3 3 N\n")]
+    #[case("grid 0 0 5 3\n6 3 E\n")]
+    #[case("grid 0 0 5 3\n3 3 Q\n")]
+    fn test_bad_scent_files(#[case] input: &str) {
+        assert!(ScentFile::from_str(input).is_err())
+    }
+
+    #[test]
+    fn test_scents_actions() {
+        let first = temp_path("scents-first");
+        let second = temp_path("scents-second");
+        let merged = temp_path("scents-merged");
+        fs::write(&first, "grid 0 0 5 3\n3 3 N\n").unwrap();
+        fs::write(&second, "# comment\ngrid 0 0 5 3\n\n0 3 W\n3 3 N\n").unwrap();
+
+        let output = run_scents_action(&ScentsAction::Merge {
+            target: merged.clone(),
+            sources: vec![first.clone(), second.clone()],
+        })
+        .unwrap();
+        assert!(output.starts_with("2 scents"));
+
+        let listed = run_scents_action(&ScentsAction::List(merged.clone())).unwrap();
+        assert_eq!(
+            listed,
+            "# martian-robots scents\ngrid 0 0 5 3\n0 3 W\n3 3 N\n"
+        );
+
+        run_scents_action(&ScentsAction::Clear(merged.clone())).unwrap();
+        assert!(ScentFile::read(&merged).unwrap().scents.is_empty());
+
+        fs::write(&second, "grid 0 0 7 7\n0 3 W\n").unwrap();
+        assert!(run_scents_action(&ScentsAction::Merge {
+            target: merged.clone(),
+            sources: vec![second.clone()],
+        })
+        .is_err());
+
+        for path in [first, second, merged] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    fn second_robot_status(policy: ScentPolicy, lost: &str, next: &str) -> RobotStatus {
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into()).with_scent_policy(policy);
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+
+        let first = Robot::new()
+            .set_start_position(position(lost))
+            .update_commands(vec![RobotCommands::Forward])
+            .process_all_commands(&plateau, &mut scent_tracker);
+        assert_eq!(first.robot_status, RobotStatus::Lost);
+
+        Robot::new()
+            .set_start_position(position(next))
+            .update_commands(vec![RobotCommands::Forward])
+            .process_all_commands(&plateau, &mut scent_tracker)
+            .robot_status
+    }
+
+    #[rstest]
+    // same square, same heading is protected whatever the policy
+    #[case(ScentPolicy::Exact, "5 3 N", "5 3 N", RobotStatus::Alive)]
+    #[case(ScentPolicy::Square, "5 3 N", "5 3 N", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 3 N", "5 3 N", RobotStatus::Alive)]
+    // the other deadly heading out of the same corner
+    #[case(ScentPolicy::Exact, "5 3 N", "5 3 E", RobotStatus::Lost)]
+    #[case(ScentPolicy::Square, "5 3 N", "5 3 E", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 3 N", "5 3 E", RobotStatus::Lost)]
+    #[case(ScentPolicy::Exact, "0 0 S", "0 0 W", RobotStatus::Lost)]
+    #[case(ScentPolicy::Square, "0 0 S", "0 0 W", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "0 0 S", "0 0 W", RobotStatus::Lost)]
+    #[case(ScentPolicy::Exact, "0 3 W", "0 3 N", RobotStatus::Lost)]
+    #[case(ScentPolicy::Square, "0 3 W", "0 3 N", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "0 3 W", "0 3 N", RobotStatus::Lost)]
+    // diagonals share the side they cross with straight moves
+    #[case(ScentPolicy::Exact, "5 2 E", "5 2 NE", RobotStatus::Lost)]
+    #[case(ScentPolicy::Square, "5 2 E", "5 2 NE", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 2 E", "5 2 NE", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 3 NE", "5 3 N", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 3 NE", "5 3 E", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 3 N", "5 3 NE", RobotStatus::Alive)]
+    #[case(ScentPolicy::Exact, "5 3 NE", "5 3 E", RobotStatus::Lost)]
+    #[case(ScentPolicy::Edge, "5 2 E", "5 2 SE", RobotStatus::Alive)]
+    #[case(ScentPolicy::Edge, "5 2 E", "4 3 NE", RobotStatus::Lost)]
+    fn test_scent_policy_corners(
+        #[case] policy: ScentPolicy,
+        #[case] lost: &str,
+        #[case] next: &str,
+        #[case] expected: RobotStatus,
+    ) {
+        assert_eq!(second_robot_status(policy, lost, next), expected)
+    }
+
+    #[rstest]
+    #[case(ScentPolicy::Square)]
+    #[case(ScentPolicy::Edge)]
+    fn test_scented_square_still_allows_moves_inward(#[case] policy: ScentPolicy) {
+        let plateau = Plateau::new(Coordinates { x: 5, y: 3 }.into()).with_scent_policy(policy);
+        let mut scent_tracker: HashSet<RobotPosition> = HashSet::new();
+        scent_tracker.insert(position("5 3 N"));
+
+        let robot = Robot::new()
+            .set_start_position(position("5 3 S"))
+            .update_commands(vec![RobotCommands::Forward])
+            .process_all_commands(&plateau, &mut scent_tracker);
+
+        assert_eq!(robot.position, position("5 2 S"));
+    }
+
+    #[rstest]
+    #[case(ScentDecay::Robots(1), MissionClock { robot: 3, tick: 40, ..Default::default() }, true)]
+    #[case(ScentDecay::Robots(1), MissionClock { robot: 4, tick: 40, ..Default::default() }, false)]
+    #[case(ScentDecay::Ticks(5), MissionClock { robot: 9, tick: 15, ..Default::default() }, true)]
+    #[case(ScentDecay::Ticks(5), MissionClock { robot: 3, tick: 16, ..Default::default() }, false)]
+    fn test_scent_decay(
+        #[case] decay: ScentDecay,
+        #[case] now: MissionClock,
+        #[case] honoured: bool,
+    ) {
+        let mut scent_log = ScentLog::new(HashSet::new(), Some(decay));
+        scent_log.set_clock(MissionClock {
+            robot: 2,
+            tick: 10,
+            ..Default::default()
+        });
+        scent_log.insert(position("3 3 N"));
+
+        scent_log.set_clock(now);
+
+        assert_eq!(scent_log.contains(&position("3 3 N")), honoured);
+        assert_eq!(scent_log.active().len(), honoured as usize);
+        assert_eq!(scent_log.expired().len(), !honoured as usize);
+    }
+
+    #[test]
+    fn test_scent_log_records_robot_and_tick() {
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        scent_log.set_clock(MissionClock {
+            robot: 1,
+            tick: 7,
+            ..Default::default()
+        });
+        scent_log.insert(position("3 3 N"));
+        scent_log.set_clock(MissionClock {
+            robot: 50,
+            tick: 500,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            scent_log.active(),
+            vec![Scent {
+                position: position("3 3 N"),
+                laid: MissionClock {
+                    robot: 1,
+                    tick: 7,
+                    ..Default::default()
+                },
+            }]
+        );
+        assert!(scent_log.expired().is_empty());
+    }
+
+    #[test]
+    fn test_saved_by_names_the_scent_that_was_laid() {
+        let plateau =
+            Plateau::new(Coordinates { x: 5, y: 3 }.into()).with_scent_policy(ScentPolicy::Square);
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        scent_log.set_clock(MissionClock {
+            robot: 0,
+            line: 3,
+            ..Default::default()
+        });
+        Robot::new()
+            .set_start_position(position("5 3 N"))
+            .update_commands(vec![RobotCommands::Forward])
+            .process_all_commands(&plateau, &mut scent_log);
+
+        scent_log.set_clock(MissionClock {
+            robot: 1,
+            line: 5,
+            tick: 1,
+            ..Default::default()
+        });
+        Robot::new()
+            .set_start_position(position("5 3 E"))
+            .update_commands(vec![RobotCommands::Forward])
+            .process_all_commands(&plateau, &mut scent_log);
+
+        assert_eq!(
+            scent_log.saved_by(),
+            &[SavedBy {
+                saved: MissionClock {
+                    robot: 1,
+                    line: 5,
+                    tick: 1,
+                    ..Default::default()
+                },
+                scent: Scent {
+                    position: position("5 3 N"),
+                    laid: MissionClock {
+                        robot: 0,
+                        line: 3,
+                        ..Default::default()
+                    },
+                },
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case("robots 3", ScentDecay::Robots(3))]
+    #[case("ticks 20", ScentDecay::Ticks(20))]
+    fn test_scent_decay_from_str(#[case] input: &str, #[case] expected: ScentDecay) {
+        assert_eq!(ScentDecay::from_str(input).unwrap(), expected)
+    }
+
+    #[rstest]
+    #[case("robots")]
+    #[case("days 3")]
+    #[case("ticks -1")]
+    fn test_scent_decay_err_from_str(#[case] input: &str) {
+        assert!(ScentDecay::from_str(input).is_err())
+    }
+}
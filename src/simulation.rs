@@ -0,0 +1,1139 @@
+use crate::energy::EnergyCosts;
+use crate::mission_instructions::{Command, Coordinates, RobotCommands};
+use crate::models::RobotModel;
+use crate::plateau::Plateau;
+use crate::robots::{Robot, RobotPosition, RobotStatus};
+use crate::scents::{
+    KnownScents, LaidScent, MissionClock, ScentBlind, ScentLog, ScentMark, ScentStore,
+};
+use anyhow::anyhow;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// The order robots take their turns in.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Schedule {
+    /// Each robot carries out all of its commands before the next is dropped.
+    #[default]
+    Sequential,
+    /// Every robot still going carries out one command per tick, in the order
+    /// they were given.
+    RoundRobin,
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "sequential" => Ok(Schedule::Sequential),
+            "round-robin" => Ok(Schedule::RoundRobin),
+            _ => Err(anyhow!(
+                "Schedule must be sequential or round-robin, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Sequential => write!(f, "sequential"),
+            Schedule::RoundRobin => write!(f, "round-robin"),
+        }
+    }
+}
+
+/// What happens when a robot drives into a square another robot is on.
+/// Robots that have finished their commands stay parked where they stopped.
+/// A robot dropped onto a square another robot is on collides with it too,
+/// but as nothing can keep it off the square `Block` only reports that.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum CollisionPolicy {
+    /// The move goes ahead and the collision is only reported.
+    #[default]
+    Warn,
+    /// The robot waits where it is instead.
+    Block,
+    /// Both robots end as `COLLIDED`, on the square they collided on.
+    Destroy,
+}
+
+impl FromStr for CollisionPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "warn" => Ok(CollisionPolicy::Warn),
+            "block" => Ok(CollisionPolicy::Block),
+            "destroy" => Ok(CollisionPolicy::Destroy),
+            _ => Err(anyhow!(
+                "Collision policy must be warn, block or destroy, got {}",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CollisionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollisionPolicy::Warn => write!(f, "warn"),
+            CollisionPolicy::Block => write!(f, "block"),
+            CollisionPolicy::Destroy => write!(f, "destroy"),
+        }
+    }
+}
+
+/// A robot driving into the square `other` was on.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Collision {
+    pub tick: usize,
+    pub robot: usize,
+    pub other: usize,
+    pub at: Coordinates,
+}
+
+/// What one tick did: the command the robot carried out, or couldn't for lack
+/// of energy, where that left it and any scents it laid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub tick: usize,
+    pub robot: usize,
+    pub command: RobotCommands,
+    pub position: RobotPosition,
+    pub status: RobotStatus,
+    pub scents: Vec<RobotPosition>,
+}
+
+impl FromStr for Step {
+    type Err = anyhow::Error;
+
+    /// Reads a step written out by `Display`, e.g.
+    /// `tick 15 robot 1 F -> 3 3 N LOST scent 3 3 N`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let step = match words[..] {
+            ["tick", tick, "robot", robot, command, "->", x, y, orientation, status, ref scents @ ..] => {
+                Step {
+                    tick: tick.parse()?,
+                    robot: robot.parse()?,
+                    command: RobotCommands::from_str(command)?,
+                    position: RobotPosition::from_str(&format!("{} {} {}", x, y, orientation))?,
+                    status: RobotStatus::from_str(status)?,
+                    scents: scents
+                        .chunks(4)
+                        .map(|scent| match scent {
+                            ["scent", x, y, orientation] => {
+                                RobotPosition::from_str(&format!("{} {} {}", x, y, orientation))
+                            }
+                            _ => Err(anyhow!("Expected scent x y heading")),
+                        })
+                        .collect::<Result<Vec<RobotPosition>, anyhow::Error>>()?,
+                }
+            }
+            _ => return Err(anyhow!("Not a step: {}", input)),
+        };
+        Ok(step)
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tick {} robot {} {} -> {} {}",
+            self.tick, self.robot, self.command, self.position, self.status
+        )?;
+        for scent in &self.scents {
+            write!(f, " scent {}", scent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every robot in its final state and anything that happened between them
+/// along the way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissionOutcome {
+    pub robots: Vec<Robot>,
+    pub collisions: Vec<Collision>,
+    /// The squares each robot stood on, in order, starting with the one it
+    /// was dropped on.
+    pub paths: Vec<Vec<Coordinates>>,
+    /// Every tick of the mission in the order it ran.
+    pub steps: Vec<Step>,
+}
+
+/// Runs the mission's robots in the order set by `schedule`. A scent laid in
+/// one tick is picked up by robots from the next tick on.
+pub fn run_mission<S: ScentStore>(
+    command: &Command,
+    schedule: Schedule,
+    scent_log: &mut ScentLog<S>,
+) -> MissionOutcome {
+    let mut simulation = Simulation::new(command, schedule, scent_log);
+    while simulation.tick() {}
+    MissionOutcome {
+        robots: simulation.robots,
+        collisions: simulation.collisions,
+        paths: simulation.paths,
+        steps: simulation.steps,
+    }
+}
+
+/// A robot as it was before a tick changed it.
+#[derive(Debug, Clone, PartialEq)]
+struct RobotBefore {
+    index: usize,
+    robot: Robot,
+    dropped: bool,
+    commands_run: usize,
+    /// The length of its path, counting squares let go by `trim`.
+    path: usize,
+}
+
+/// Everything one tick changed, enough to take it back.
+#[derive(Debug, Clone, PartialEq)]
+struct TickDelta {
+    tick: usize,
+    next_robot: usize,
+    robots: Vec<RobotBefore>,
+    collisions: usize,
+    /// Steps taken, counting those let go by `trim`.
+    steps: usize,
+    scents: ScentMark,
+    laid: Vec<LaidScent>,
+}
+
+/// The whole state of a mission between ticks. Steps and paths are trimmed
+/// before a snapshot is taken so only their latest entries are kept, and the
+/// collisions, which only ever grow, are cut back to the number there were.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot<S> {
+    tick: usize,
+    next_robot: usize,
+    robots: Vec<Robot>,
+    dropped: Vec<bool>,
+    commands_run: Vec<usize>,
+    paths: Vec<Vec<Coordinates>>,
+    paths_trimmed: Vec<usize>,
+    collisions: usize,
+    steps: Vec<Step>,
+    steps_trimmed: usize,
+    scent_log: ScentLog<S>,
+}
+
+/// The state of a mission part way through.
+struct Simulation<'a, S> {
+    plateau: Plateau,
+    collision_policy: CollisionPolicy,
+    energy_costs: EnergyCosts,
+    schedule: Schedule,
+    scent_log: &'a mut ScentLog<S>,
+    /// Ticks carried out so far.
+    tick: usize,
+    /// The robot whose turn it is under the sequential schedule.
+    next_robot: usize,
+    robots: Vec<Robot>,
+    /// Whether each robot has been dropped on the plateau yet.
+    dropped: Vec<bool>,
+    /// How many commands each robot has carried out.
+    commands_run: Vec<usize>,
+    collisions: Vec<Collision>,
+    paths: Vec<Vec<Coordinates>>,
+    /// How many squares have been let go from the front of each path.
+    paths_trimmed: Vec<usize>,
+    steps: Vec<Step>,
+    /// How many steps have been let go from the front of `steps`.
+    steps_trimmed: usize,
+    /// What the current tick has changed so far, when it may be taken back.
+    delta: Option<TickDelta>,
+}
+
+impl<'a, S: ScentStore> Simulation<'a, S> {
+    fn new(command: &Command, schedule: Schedule, scent_log: &'a mut ScentLog<S>) -> Self {
+        Simulation {
+            plateau: command.plateau(),
+            collision_policy: command.collision_policy,
+            energy_costs: command.energy_costs,
+            schedule,
+            scent_log,
+            tick: 0,
+            next_robot: 0,
+            robots: command.robots.clone(),
+            dropped: vec![false; command.robots.len()],
+            commands_run: vec![0; command.robots.len()],
+            collisions: vec![],
+            paths: command
+                .robots
+                .iter()
+                .map(|robot| vec![robot.position.coordinates.clone()])
+                .collect(),
+            paths_trimmed: vec![0; command.robots.len()],
+            steps: vec![],
+            steps_trimmed: 0,
+            delta: None,
+        }
+    }
+
+    /// Carries out the next tick, returning false once every robot has
+    /// finished. Under the sequential schedule each command a robot carries
+    /// out is one tick, under round robin one tick is a round in which every
+    /// active robot carries out one command.
+    fn tick(&mut self) -> bool {
+        match self.schedule {
+            Schedule::Sequential => {
+                while self.next_robot < self.robots.len() {
+                    let index = self.next_robot;
+                    if !self.dropped[index] {
+                        self.drop_robot(index);
+                    }
+                    if self.robots[index].is_active() {
+                        self.step(index, self.tick);
+                        self.tick += 1;
+                        return true;
+                    }
+                    self.next_robot += 1;
+                }
+                false
+            }
+            Schedule::RoundRobin => {
+                // every robot is dropped before the first round
+                for index in 0..self.robots.len() {
+                    if !self.dropped[index] {
+                        self.drop_robot(index);
+                    }
+                }
+                if !self.robots.iter().any(Robot::is_active) {
+                    return false;
+                }
+                for index in 0..self.robots.len() {
+                    if self.robots[index].is_active() {
+                        self.step(index, self.tick);
+                    }
+                }
+                self.tick += 1;
+                true
+            }
+        }
+    }
+
+    /// Starts keeping what the next tick changes, for `undo`.
+    fn begin_tick(&mut self) {
+        self.delta = Some(TickDelta {
+            tick: self.tick,
+            next_robot: self.next_robot,
+            robots: vec![],
+            collisions: self.collisions.len(),
+            steps: self.steps_taken(),
+            scents: self.scent_log.mark(),
+            laid: vec![],
+        });
+    }
+
+    fn end_tick(&mut self) -> Option<TickDelta> {
+        let mut delta = self.delta.take()?;
+        delta.laid = self.scent_log.laid_since_mark();
+        Some(delta)
+    }
+
+    /// Keeps a robot as it is before the tick first changes it.
+    fn remember(&mut self, index: usize) {
+        let path = self.path_length(index);
+        let Some(delta) = self.delta.as_mut() else {
+            return;
+        };
+        if delta.robots.iter().all(|before| before.index != index) {
+            delta.robots.push(RobotBefore {
+                index,
+                robot: self.robots[index].clone(),
+                dropped: self.dropped[index],
+                commands_run: self.commands_run[index],
+                path,
+            });
+        }
+    }
+
+    fn undo(&mut self, delta: TickDelta) {
+        for before in delta.robots.into_iter().rev() {
+            self.robots[before.index] = before.robot;
+            self.dropped[before.index] = before.dropped;
+            self.commands_run[before.index] = before.commands_run;
+            self.paths[before.index].truncate(before.path - self.paths_trimmed[before.index]);
+        }
+        self.collisions.truncate(delta.collisions);
+        self.steps.truncate(delta.steps - self.steps_trimmed);
+        self.scent_log.undo(&delta.scents, &delta.laid);
+        self.tick = delta.tick;
+        self.next_robot = delta.next_robot;
+    }
+
+    fn steps_taken(&self) -> usize {
+        self.steps_trimmed + self.steps.len()
+    }
+
+    fn path_length(&self, index: usize) -> usize {
+        self.paths_trimmed[index] + self.paths[index].len()
+    }
+
+    /// Lets go of every step and every square of each path apart from the
+    /// latest, once nothing before them can be taken back.
+    fn trim(&mut self) {
+        let old = self.steps.len().saturating_sub(1);
+        self.steps.drain(..old);
+        self.steps_trimmed += old;
+        for (path, trimmed) in self.paths.iter_mut().zip(&mut self.paths_trimmed) {
+            let old = path.len().saturating_sub(1);
+            path.drain(..old);
+            *trimmed += old;
+        }
+    }
+
+    /// The robot, other than `index`, still on the plateau at `coordinates`.
+    fn robot_at(&self, coordinates: &Coordinates, index: usize) -> Option<usize> {
+        (0..self.robots.len()).find(|&other| {
+            other != index
+                && self.dropped[other]
+                && self.robots[other].robot_status != RobotStatus::Lost
+                && self.robots[other].position.coordinates == *coordinates
+        })
+    }
+
+    /// Puts a robot on the plateau, colliding with any robot already on its
+    /// square.
+    fn drop_robot(&mut self, index: usize) {
+        self.remember(index);
+        self.dropped[index] = true;
+        let at = self.robots[index].position.coordinates.clone();
+        let Some(other) = self.robot_at(&at, index) else {
+            return;
+        };
+        self.collisions.push(Collision {
+            tick: self.tick,
+            robot: index,
+            other,
+            at,
+        });
+        if self.collision_policy == CollisionPolicy::Destroy {
+            self.robots[index].robot_status = RobotStatus::Collided;
+            self.remember(other);
+            self.robots[other].robot_status = RobotStatus::Collided;
+        }
+    }
+
+    /// Whether the robot's next command is a forward move that would leave
+    /// it where it is, worked out without laying or using any scents.
+    fn is_blocked(&self, index: usize, model: &RobotModel) -> bool {
+        let robot = &self.robots[index];
+        if robot.robot_commands[0] != RobotCommands::Forward {
+            return false;
+        }
+        let moved = if model.scent_aware {
+            drive(
+                &self.plateau,
+                robot,
+                model,
+                &mut KnownScents(&*self.scent_log),
+            )
+        } else {
+            let no_scents: HashSet<RobotPosition> = HashSet::new();
+            drive(&self.plateau, robot, model, &mut KnownScents(&no_scents))
+        };
+        moved.robot_status == RobotStatus::Alive
+            && (moved.position.coordinates == robot.position.coordinates
+                || self.collision_policy == CollisionPolicy::Block
+                    && self.robot_at(&moved.position.coordinates, index).is_some())
+    }
+
+    fn step(&mut self, index: usize, tick: usize) {
+        self.remember(index);
+        self.scent_log.set_clock(MissionClock {
+            robot: index,
+            command: self.commands_run[index],
+            line: self.robots[index].line,
+            tick,
+        });
+        let robot = &self.robots[index];
+        let model = robot.model.clone().unwrap_or_default();
+        let costs = model.costs(self.energy_costs);
+        let command = robot.robot_commands[0].clone();
+        if robot
+            .energy
+            .is_some_and(|energy| energy < costs.used(&command, self.is_blocked(index, &model)))
+        {
+            self.robots[index].robot_status = RobotStatus::Depleted;
+            self.steps.push(Step {
+                tick,
+                robot: index,
+                command,
+                position: self.robots[index].position.clone(),
+                status: RobotStatus::Depleted,
+                scents: vec![],
+            });
+            return;
+        }
+        let start = robot.position.clone();
+        let mut moved = if model.scent_aware {
+            drive(&self.plateau, robot, &model, self.scent_log)
+        } else {
+            drive(
+                &self.plateau,
+                robot,
+                &model,
+                &mut ScentBlind(self.scent_log),
+            )
+        };
+        self.commands_run[index] += 1;
+
+        let other = if moved.robot_status == RobotStatus::Alive
+            && moved.position.coordinates != start.coordinates
+        {
+            self.robot_at(&moved.position.coordinates, index)
+        } else {
+            None
+        };
+        if let Some(other) = other {
+            self.collisions.push(Collision {
+                tick,
+                robot: index,
+                other,
+                at: moved.position.coordinates.clone(),
+            });
+            match self.collision_policy {
+                CollisionPolicy::Warn => {}
+                CollisionPolicy::Block => moved.position = start.clone(),
+                CollisionPolicy::Destroy => {
+                    moved.robot_status = RobotStatus::Collided;
+                    self.remember(other);
+                    self.robots[other].robot_status = RobotStatus::Collided;
+                }
+            }
+        }
+        let blocked = moved.robot_status == RobotStatus::Alive
+            && moved.position.coordinates == start.coordinates;
+        let used = costs.used(&command, blocked);
+        moved.energy = moved.energy.map(|energy| energy.saturating_sub(used));
+        if moved.robot_status != RobotStatus::Lost
+            && moved.position.coordinates != start.coordinates
+        {
+            self.paths[index].push(moved.position.coordinates.clone());
+        }
+        self.steps.push(Step {
+            tick,
+            robot: index,
+            command,
+            position: moved.position.clone(),
+            status: moved.robot_status.clone(),
+            scents: self.scent_log.laid_now(),
+        });
+        self.robots[index] = moved;
+    }
+}
+
+/// The robot after carrying out its next command, before any other robot
+/// gets in the way.
+fn drive<S: ScentStore>(
+    plateau: &Plateau,
+    robot: &Robot,
+    model: &RobotModel,
+    scents: &mut S,
+) -> Robot {
+    if robot.robot_commands[0] == RobotCommands::Forward
+        && !model.diagonal
+        && robot.position.orientation.is_diagonal()
+    {
+        // the model can't drive this way so the command goes nowhere
+        let mut robot = robot.clone();
+        robot.robot_commands.remove(0);
+        robot
+    } else {
+        robot.clone().process_robot_command(plateau, scents)
+    }
+}
+
+/// How many ticks apart `Rewindable` starts out taking snapshots.
+pub const SNAPSHOT_INTERVAL: usize = 64;
+
+/// At most this many snapshots are kept. When there would be more, every
+/// other one is dropped and they are taken half as often from then on.
+const MAX_SNAPSHOTS: usize = 32;
+
+/// A mission that can be stepped through a tick at a time, backwards as well
+/// as forwards. The ticks since the latest snapshot can each be taken back;
+/// going further back starts again from an earlier snapshot and runs forward
+/// to the tick asked for. Only the steps and squares moved to since the
+/// latest snapshot are kept, so memory stays bounded however long the
+/// mission.
+pub struct Rewindable<'a, S> {
+    simulation: Simulation<'a, S>,
+    /// Oldest first, starting with the mission before its first tick.
+    snapshots: Vec<Snapshot<S>>,
+    /// The ticks since the latest snapshot, oldest first.
+    deltas: Vec<TickDelta>,
+    /// How many ticks apart snapshots are taken.
+    interval: usize,
+}
+
+impl<'a, S: ScentStore + Clone> Rewindable<'a, S> {
+    pub fn new(
+        command: &Command,
+        schedule: Schedule,
+        scent_log: &'a mut ScentLog<S>,
+        interval: usize,
+    ) -> Self {
+        let mut rewindable = Rewindable {
+            simulation: Simulation::new(command, schedule, scent_log),
+            snapshots: vec![],
+            deltas: vec![],
+            interval: interval.max(1),
+        };
+        rewindable.take_snapshot();
+        rewindable
+    }
+
+    /// Ticks carried out so far.
+    pub fn tick(&self) -> usize {
+        self.simulation.tick
+    }
+
+    pub fn robots(&self) -> &[Robot] {
+        &self.simulation.robots
+    }
+
+    /// The step the latest tick carried out.
+    pub fn last_step(&self) -> Option<&Step> {
+        self.simulation.steps.last()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.simulation.robots.iter().any(Robot::is_active)
+    }
+
+    /// Carries out the next tick, returning false once the mission is over.
+    pub fn step(&mut self) -> bool {
+        self.simulation.begin_tick();
+        let ticked = self.simulation.tick();
+        let Some(delta) = self.simulation.end_tick() else {
+            return ticked;
+        };
+        if !ticked {
+            // only robots with nothing to do were dropped, which can still
+            // have collided, so it is kept to take back without being a tick
+            if !delta.robots.is_empty() {
+                self.deltas.push(delta);
+            }
+            return false;
+        }
+        self.deltas.push(delta);
+        if self.simulation.tick.is_multiple_of(self.interval) {
+            self.take_snapshot();
+        }
+        true
+    }
+
+    /// Goes back `ticks` ticks, or to the start of the mission.
+    pub fn rewind(&mut self, ticks: usize) {
+        self.seek(self.tick().saturating_sub(ticks));
+    }
+
+    /// Goes backwards or forwards to just after tick `tick - 1`, or to the end
+    /// of the mission if it finishes sooner.
+    pub fn seek(&mut self, tick: usize) {
+        if tick < self.tick() {
+            if self
+                .snapshots
+                .last()
+                .is_some_and(|latest| latest.tick > tick)
+            {
+                self.snapshots.retain(|snapshot| snapshot.tick <= tick);
+                self.deltas.clear();
+                self.restore();
+            }
+            while self.tick() > tick {
+                match self.deltas.pop() {
+                    Some(delta) => self.simulation.undo(delta),
+                    None => break,
+                }
+            }
+        }
+        while self.tick() < tick && self.step() {}
+    }
+
+    fn take_snapshot(&mut self) {
+        self.simulation.trim();
+        let simulation = &self.simulation;
+        self.snapshots.push(Snapshot {
+            tick: simulation.tick,
+            next_robot: simulation.next_robot,
+            robots: simulation.robots.clone(),
+            dropped: simulation.dropped.clone(),
+            commands_run: simulation.commands_run.clone(),
+            paths: simulation.paths.clone(),
+            paths_trimmed: simulation.paths_trimmed.clone(),
+            collisions: simulation.collisions.len(),
+            steps: simulation.steps.clone(),
+            steps_trimmed: simulation.steps_trimmed,
+            scent_log: simulation.scent_log.clone(),
+        });
+        self.deltas.clear();
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            let mut index = 0;
+            self.snapshots.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.interval *= 2;
+        }
+    }
+
+    /// Puts the mission back to the latest snapshot.
+    fn restore(&mut self) {
+        let snapshot = self
+            .snapshots
+            .last()
+            .expect("the first snapshot is never dropped");
+        let simulation = &mut self.simulation;
+        simulation.tick = snapshot.tick;
+        simulation.next_robot = snapshot.next_robot;
+        simulation.robots = snapshot.robots.clone();
+        simulation.dropped = snapshot.dropped.clone();
+        simulation.commands_run = snapshot.commands_run.clone();
+        simulation.paths = snapshot.paths.clone();
+        simulation.paths_trimmed = snapshot.paths_trimmed.clone();
+        simulation.collisions.truncate(snapshot.collisions);
+        simulation.steps = snapshot.steps.clone();
+        simulation.steps_trimmed = snapshot.steps_trimmed;
+        *simulation.scent_log = snapshot.scent_log.clone();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mission_instructions::Coordinates;
+    use crate::parser::parse_input_to_command;
+    use crate::robots::RobotStatus;
+    use crate::scents::{MissionClock, ScentLog};
+    use crate::simulation::{
+        run_mission, Collision, CollisionPolicy, Rewindable, Schedule, MAX_SNAPSHOTS,
+    };
+    use rstest::*;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_run_mission_stamps_scents() {
+        let command = parse_input_to_command(
+            "5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n0 3 W\nLLFFFLFLFL",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let robots = run_mission(&command, Schedule::Sequential, &mut scent_log).robots;
+
+        assert_eq!(robots[1].robot_status, RobotStatus::Lost);
+        // robot 0 takes 8 ticks, robot 1 is lost on its 8th command
+        assert_eq!(
+            scent_log.active()[0].laid,
+            MissionClock {
+                robot: 1,
+                command: 7,
+                line: 6,
+                tick: 15
+            }
+        );
+    }
+
+    #[test]
+    fn test_saved_by_points_at_the_lost_robot() {
+        let command = parse_input_to_command(
+            "5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n0 3 W\nLLFFFLFLFL",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        let saved_by = scent_log.saved_by();
+        assert_eq!(saved_by.len(), 1);
+        assert_eq!(saved_by[0].saved.robot, 2);
+        assert_eq!(saved_by[0].saved.command, 6);
+        assert_eq!(saved_by[0].saved.line, 9);
+        assert_eq!(saved_by[0].scent.laid.robot, 1);
+        assert_eq!(saved_by[0].scent.laid.line, 6);
+    }
+
+    #[test]
+    fn test_decayed_scent_no_longer_saves_robots() {
+        let mission = "5 3\n3 3 N\nF\n\n1 1 E\nF\n\n3 3 N\nF";
+
+        let command = parse_input_to_command(&format!("decay robots 2\n{}", mission)).unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let robots = run_mission(&command, Schedule::Sequential, &mut scent_log).robots;
+        assert_eq!(robots[2].robot_status, RobotStatus::Alive);
+
+        let command = parse_input_to_command(&format!("decay robots 1\n{}", mission)).unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let robots = run_mission(&command, Schedule::Sequential, &mut scent_log).robots;
+        assert_eq!(robots[2].robot_status, RobotStatus::Lost);
+        // the second loss lays the scent again
+        assert_eq!(scent_log.active()[0].laid.robot, 2);
+    }
+
+    #[rstest]
+    #[case(Schedule::Sequential, RobotStatus::Alive)]
+    #[case(Schedule::RoundRobin, RobotStatus::Lost)]
+    fn test_scent_from_the_same_tick_is_not_seen(
+        #[case] schedule: Schedule,
+        #[case] second_robot: RobotStatus,
+    ) {
+        let command = parse_input_to_command("5 3\n3 3 N\nF\n\n3 3 N\nF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let robots = run_mission(&command, schedule, &mut scent_log).robots;
+
+        assert_eq!(robots[0].robot_status, RobotStatus::Lost);
+        assert_eq!(robots[1].robot_status, second_robot);
+    }
+
+    #[test]
+    fn test_round_robin_interleaves_robots() {
+        let command = parse_input_to_command("5 3\n3 3 N\nF\n\n3 2 N\nFF\n\n0 0 E\nFFF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let robots = run_mission(&command, Schedule::RoundRobin, &mut scent_log).robots;
+
+        // the second robot reaches the edge a tick after the first was lost
+        assert_eq!(robots[1].robot_status, RobotStatus::Alive);
+        assert_eq!(robots[1].position.to_string(), "3 3 N");
+        assert_eq!(robots[2].position.to_string(), "3 0 E");
+        let saved_by = scent_log.saved_by();
+        assert_eq!(saved_by[0].saved.tick, 1);
+        assert_eq!(saved_by[0].saved.command, 1);
+        assert_eq!(saved_by[0].scent.laid.tick, 0);
+    }
+
+    #[rstest]
+    #[case("sequential", Schedule::Sequential)]
+    #[case("round-robin", Schedule::RoundRobin)]
+    fn test_schedule_from_str(#[case] input: &str, #[case] expected: Schedule) {
+        assert_eq!(Schedule::from_str(input).unwrap(), expected);
+        assert_eq!(expected.to_string(), input);
+    }
+
+    #[rstest]
+    #[case("warn", "3 2 N", RobotStatus::Alive, RobotStatus::Alive)]
+    #[case("block", "3 0 N", RobotStatus::Alive, RobotStatus::Alive)]
+    #[case("destroy", "3 1 N", RobotStatus::Collided, RobotStatus::Collided)]
+    fn test_collision_with_parked_robot(
+        #[case] policy: &str,
+        #[case] end: &str,
+        #[case] mover: RobotStatus,
+        #[case] parked: RobotStatus,
+    ) {
+        let command = parse_input_to_command(&format!(
+            "on-collision {}\n5 3\n3 1 E\nL\n\n3 0 N\nFF",
+            policy
+        ))
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+
+        assert_eq!(outcome.robots[0].robot_status, parked);
+        assert_eq!(outcome.robots[1].robot_status, mover);
+        assert_eq!(outcome.robots[1].position.to_string(), end);
+        assert_eq!(
+            outcome.collisions[0],
+            Collision {
+                tick: 1,
+                robot: 1,
+                other: 0,
+                at: Coordinates { x: 3, y: 1 },
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(Schedule::Sequential, "warn", 1, RobotStatus::Alive)]
+    #[case(Schedule::Sequential, "destroy", 1, RobotStatus::Collided)]
+    #[case(Schedule::RoundRobin, "block", 0, RobotStatus::Alive)]
+    #[case(Schedule::RoundRobin, "destroy", 0, RobotStatus::Collided)]
+    fn test_robots_dropped_on_the_same_square_collide(
+        #[case] schedule: Schedule,
+        #[case] policy: &str,
+        #[case] tick: usize,
+        #[case] status: RobotStatus,
+    ) {
+        let command = parse_input_to_command(&format!(
+            "on-collision {}\n5 3\n1 1 N\nL\n\n1 1 E\nF",
+            policy
+        ))
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let outcome = run_mission(&command, schedule, &mut scent_log);
+
+        assert_eq!(
+            outcome.collisions,
+            vec![Collision {
+                tick,
+                robot: 1,
+                other: 0,
+                at: Coordinates { x: 1, y: 1 },
+            }]
+        );
+        assert_eq!(outcome.robots[0].robot_status, status);
+        assert_eq!(outcome.robots[1].robot_status, status);
+    }
+
+    #[test]
+    fn test_robots_not_yet_dropped_are_not_in_the_way() {
+        let command =
+            parse_input_to_command("on-collision block\n5 3\n0 0 E\nFF\n\n1 0 N\nF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let sequential = run_mission(&command, Schedule::Sequential, &mut scent_log);
+        assert_eq!(sequential.robots[0].position.to_string(), "2 0 E");
+        assert!(sequential.collisions.is_empty());
+
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let round_robin = run_mission(&command, Schedule::RoundRobin, &mut scent_log);
+        // the second robot is still on 1 0 when the first tries to move
+        assert_eq!(round_robin.robots[0].position.to_string(), "1 0 E");
+        assert_eq!(round_robin.collisions.len(), 1);
+    }
+
+    #[rstest]
+    #[case("warn", CollisionPolicy::Warn)]
+    #[case("block", CollisionPolicy::Block)]
+    #[case("destroy", CollisionPolicy::Destroy)]
+    fn test_collision_policy_from_str(#[case] input: &str, #[case] expected: CollisionPolicy) {
+        assert_eq!(CollisionPolicy::from_str(input).unwrap(), expected);
+        assert_eq!(expected.to_string(), input);
+    }
+
+    #[test]
+    fn test_robot_runs_out_of_energy() {
+        let command = parse_input_to_command(
+            "energy 6\ncost forward 2\ncost blocked 3\nobstacle 1 1\n5 3\n0 0 N\nFRFFF\n\n4 0 N\nFL",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let robots = run_mission(&command, Schedule::Sequential, &mut scent_log).robots;
+
+        // F and R leave 3 and the blocked F uses the rest
+        assert_eq!(robots[0].robot_status, RobotStatus::Depleted);
+        assert_eq!(robots[0].position.to_string(), "0 1 E");
+        assert_eq!(robots[0].energy, Some(0));
+        assert_eq!(robots[0].robot_commands.len(), 2);
+        assert_eq!(robots[1].robot_status, RobotStatus::Alive);
+        assert_eq!(robots[1].energy, Some(3));
+    }
+
+    #[rstest]
+    // enough for two blocked bumps into the obstacle
+    #[case("obstacle 0 1", RobotStatus::Alive, Some(0))]
+    // not enough for a forward move that goes anywhere
+    #[case("obstacle 3 3", RobotStatus::Depleted, Some(2))]
+    fn test_blocked_moves_only_need_the_blocked_cost(
+        #[case] obstacle: &str,
+        #[case] status: RobotStatus,
+        #[case] energy: Option<u32>,
+    ) {
+        let command = parse_input_to_command(&format!(
+            "energy 2\ncost forward 3\ncost blocked 1\n{}\n5 3\n0 0 N\nFF",
+            obstacle
+        ))
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let robots = run_mission(&command, Schedule::Sequential, &mut scent_log).robots;
+
+        assert_eq!(robots[0].robot_status, status);
+        assert_eq!(robots[0].position.to_string(), "0 0 N");
+        assert_eq!(robots[0].energy, energy);
+    }
+
+    #[test]
+    fn test_robot_models_follow_their_rules() {
+        let command = parse_input_to_command(
+            "compass 8\nenergy 10\nmodel blind scents=ignore\nmodel rail diagonal=no forward=3\n5 3\n\
+             3 3 N\nF\n\n@seer\n3 3 N\nF\n\n@mole model=blind\n3 3 N\nF\n\n@tram model=rail\n0 0 NE\nFlF",
+        )
+        .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let robots = run_mission(&command, Schedule::Sequential, &mut scent_log).robots;
+
+        assert_eq!(robots[1].robot_status, RobotStatus::Alive);
+        assert_eq!(robots[2].robot_status, RobotStatus::Lost);
+        // the diagonal move is blocked, costing 1, then the turn and the move north
+        assert_eq!(robots[3].position.to_string(), "0 1 N");
+        assert_eq!(robots[3].energy, Some(10 - 1 - 1 - 3));
+    }
+
+    #[test]
+    fn test_paths_record_squares_moved_to() {
+        let command =
+            parse_input_to_command("boundary wrap\nobstacle 1 1\n5 3\n0 0 W\nFRFRF\n\n1 0 N\nFLF")
+                .unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+
+        let paths = run_mission(&command, Schedule::Sequential, &mut scent_log).paths;
+
+        // wrapping round counts as a move, turns and blocked moves don't
+        assert_eq!(
+            paths[0],
+            vec![
+                Coordinates { x: 0, y: 0 },
+                Coordinates { x: 5, y: 0 },
+                Coordinates { x: 5, y: 1 },
+                Coordinates { x: 0, y: 1 },
+            ]
+        );
+        assert_eq!(
+            paths[1],
+            vec![Coordinates { x: 1, y: 0 }, Coordinates { x: 0, y: 0 }]
+        );
+    }
+
+    const BUSY_MISSION: &str =
+        "decay ticks 6\non-collision destroy\nenergy 30\nobstacle 2 2\n5 3\n\
+        1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n0 3 W\nLLFFFLFLFL\n\n3 3 N\nFRF\n\n\
+        4 0 N\nFFFFLFFRRFF\n\n3 1 W\nFFLFFRF";
+
+    #[rstest]
+    #[case(Schedule::Sequential, 1)]
+    #[case(Schedule::Sequential, 4)]
+    #[case(Schedule::RoundRobin, 1)]
+    #[case(Schedule::RoundRobin, 3)]
+    fn test_seeking_matches_running_straight_through(
+        #[case] schedule: Schedule,
+        #[case] interval: usize,
+    ) {
+        let command = parse_input_to_command(BUSY_MISSION).unwrap();
+        let mut straight_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let straight = run_mission(&command, schedule, &mut straight_log);
+
+        let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let mut mission = Rewindable::new(&command, schedule, &mut scent_log, interval);
+        let mut states = vec![];
+        loop {
+            let simulation = &mission.simulation;
+            states.push((
+                simulation.robots.clone(),
+                simulation.steps.last().cloned(),
+                simulation.steps_taken(),
+                simulation
+                    .paths
+                    .iter()
+                    .map(|path| path.last().cloned())
+                    .collect::<Vec<_>>(),
+                (0..simulation.paths.len())
+                    .map(|index| simulation.path_length(index))
+                    .collect::<Vec<usize>>(),
+                simulation.collisions.clone(),
+                simulation.scent_log.clone(),
+            ));
+            if !mission.step() {
+                break;
+            }
+        }
+        let ticks = mission.tick();
+        assert_eq!(ticks + 1, states.len());
+
+        for tick in [ticks - 1, 2, ticks, 0, 5, 4, ticks / 2, 1, ticks] {
+            mission.seek(tick);
+            let simulation = &mission.simulation;
+            assert_eq!(mission.tick(), tick);
+            assert_eq!(simulation.robots, states[tick].0, "robots at tick {}", tick);
+            assert_eq!(mission.last_step().cloned(), states[tick].1);
+            assert_eq!(simulation.steps_taken(), states[tick].2);
+            assert_eq!(
+                simulation
+                    .paths
+                    .iter()
+                    .map(|path| path.last().cloned())
+                    .collect::<Vec<_>>(),
+                states[tick].3
+            );
+            assert_eq!(
+                (0..simulation.paths.len())
+                    .map(|index| simulation.path_length(index))
+                    .collect::<Vec<usize>>(),
+                states[tick].4
+            );
+            assert_eq!(simulation.collisions, states[tick].5);
+            assert_eq!(
+                *simulation.scent_log, states[tick].6,
+                "scents at tick {}",
+                tick
+            );
+        }
+
+        assert!(mission.is_finished());
+        assert_eq!(mission.robots(), &straight.robots[..]);
+        assert_eq!(mission.last_step(), straight.steps.last());
+        drop(mission);
+        assert_eq!(scent_log.active(), straight_log.active());
+        assert_eq!(scent_log.saved_by(), straight_log.saved_by());
+    }
+
+    #[test]
+    fn test_rewinding_takes_back_scents() {
+        let command = parse_input_to_command("5 3\n3 3 N\nF\n\n3 3 N\nF").unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let mut mission = Rewindable::new(&command, Schedule::Sequential, &mut scent_log, 10);
+
+        mission.seek(2);
+        assert_eq!(mission.robots()[1].robot_status, RobotStatus::Alive);
+        assert_eq!(mission.simulation.scent_log.active().len(), 1);
+
+        mission.rewind(2);
+        assert_eq!(mission.tick(), 0);
+        assert_eq!(mission.robots()[0].robot_status, RobotStatus::Alive);
+        assert!(mission.simulation.scent_log.active().is_empty());
+        assert_eq!(mission.last_step(), None);
+
+        // rewinding past the start stops there, seeking past the end too
+        mission.rewind(5);
+        assert_eq!(mission.tick(), 0);
+        mission.seek(50);
+        assert_eq!(mission.tick(), 2);
+    }
+
+    #[test]
+    fn test_history_stays_bounded() {
+        // each robot drives round a square, so its path keeps growing
+        let robot = format!("0 0 N\n{}", "FRFR".repeat(25));
+        let mission = vec![robot; 40].join("\n\n");
+        let command = parse_input_to_command(&format!("5 3\n{}", mission)).unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), None);
+        let mut mission = Rewindable::new(&command, Schedule::Sequential, &mut scent_log, 1);
+
+        while mission.step() {
+            let simulation = &mission.simulation;
+            assert!(mission.snapshots.len() <= MAX_SNAPSHOTS);
+            assert!(mission.deltas.len() < mission.interval);
+            // the step before the latest snapshot and every one since
+            assert!(simulation.steps.len() <= mission.interval + 1);
+            assert!(simulation
+                .paths
+                .iter()
+                .all(|path| path.len() <= mission.interval + 1));
+        }
+
+        assert_eq!(mission.tick(), 4000);
+        assert_eq!(mission.simulation.steps_taken(), 4000);
+        assert!(mission.simulation.steps.len() < 4000 / MAX_SNAPSHOTS);
+        assert_eq!(mission.simulation.path_length(39), 51);
+        mission.seek(1234);
+        assert_eq!(mission.last_step().unwrap().tick, 1233);
+        assert!(mission.simulation.steps.len() <= mission.interval + 1);
+        assert_eq!(mission.robots()[12].robot_commands.len(), 100 - 34);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::mission_instructions::{Compass, Coordinates, RobotCommands};
+use crate::planner::Goal;
+use crate::plateau::Plateau;
+use crate::robots::{RobotPosition, RobotStatus};
+use crate::scents::{KnownScents, ScentStore, HEADINGS};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Where a robot dropped at `start` ends up after the commands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub start: RobotPosition,
+    /// The last position it was alive in.
+    pub end: RobotPosition,
+    pub status: RobotStatus,
+    /// How many commands it carried out, counting the one that lost or
+    /// crashed it.
+    pub commands_run: usize,
+}
+
+/// The side of the grid a robot drove over, or the plateau's outline when it
+/// dropped into a hole inside the grid.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Edge {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    Outline,
+}
+
+impl Edge {
+    /// The side of the plateau a forward move from `position` goes over.
+    pub fn crossed(position: &RobotPosition, plateau: &Plateau) -> Edge {
+        let Coordinates { x, y } = position.move_forward().coordinates;
+        let bounds = &plateau.bounds;
+        let north = y > bounds.upper_right.y;
+        let south = y < bounds.lower_left.y;
+        let east = x > bounds.upper_right.x;
+        let west = x < bounds.lower_left.x;
+        match (north, east, south, west) {
+            (true, true, _, _) => Edge::NorthEast,
+            (true, _, _, true) => Edge::NorthWest,
+            (_, true, true, _) => Edge::SouthEast,
+            (_, _, true, true) => Edge::SouthWest,
+            (true, _, _, _) => Edge::North,
+            (_, true, _, _) => Edge::East,
+            (_, _, true, _) => Edge::South,
+            (_, _, _, true) => Edge::West,
+            _ => Edge::Outline,
+        }
+    }
+
+    /// A short name for the edge, as used in JSON and CSV output.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Edge::North => "north",
+            Edge::NorthEast => "north-east",
+            Edge::East => "east",
+            Edge::SouthEast => "south-east",
+            Edge::South => "south",
+            Edge::SouthWest => "south-west",
+            Edge::West => "west",
+            Edge::NorthWest => "north-west",
+            Edge::Outline => "outline",
+        }
+    }
+}
+
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edge::North | Edge::East | Edge::South | Edge::West => write!(f, "{} edge", self.key()),
+            Edge::Outline => write!(f, "plateau outline"),
+            _ => write!(f, "{} corner", self.key()),
+        }
+    }
+}
+
+impl Outcome {
+    /// Which way the robot went off the plateau, for robots that were lost.
+    pub fn edge(&self, plateau: &Plateau) -> Option<Edge> {
+        Some(Edge::crossed(&self.end, plateau)).filter(|_| self.status == RobotStatus::Lost)
+    }
+}
+
+/// Runs the commands from every square and heading a robot could be dropped
+/// on, the way `Robot::process_all_commands` would, without laying scents.
+/// Starts that reach the same position are carried on together, so each
+/// distinct position is only worked out once per command. Outcomes come back
+/// bottom row first, clockwise from north on each square.
+pub fn run_from_every_start<S: ScentStore>(
+    plateau: &Plateau,
+    compass: &Compass,
+    commands: &[RobotCommands],
+    scents: &S,
+) -> Vec<Outcome> {
+    let bounds = &plateau.bounds;
+    let mut starts = vec![];
+    for y in bounds.lower_left.y..=bounds.upper_right.y {
+        for x in bounds.lower_left.x..=bounds.upper_right.x {
+            let square = Coordinates { x, y };
+            if plateau.off_plateau(&square) || plateau.is_blocked(&square) {
+                continue;
+            }
+            for heading in HEADINGS
+                .iter()
+                .filter(|heading| compass.supports_orientation(heading))
+            {
+                starts.push(RobotPosition {
+                    coordinates: square.clone(),
+                    orientation: heading.clone(),
+                });
+            }
+        }
+    }
+
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; starts.len()];
+    let mut alive: HashMap<RobotPosition, Vec<usize>> = HashMap::new();
+    for (index, start) in starts.iter().enumerate() {
+        alive.entry(start.clone()).or_default().push(index);
+    }
+
+    for (step, command) in commands.iter().enumerate() {
+        let mut still_alive: HashMap<RobotPosition, Vec<usize>> = HashMap::new();
+        for (position, group) in alive {
+            let (next, status) = command.process(position, plateau, &mut KnownScents(scents));
+            if status == RobotStatus::Alive {
+                still_alive.entry(next).or_default().extend(group);
+                continue;
+            }
+            for index in group {
+                outcomes[index] = Some(Outcome {
+                    start: starts[index].clone(),
+                    end: next.clone(),
+                    status: status.clone(),
+                    commands_run: step + 1,
+                });
+            }
+        }
+        alive = still_alive;
+    }
+    for (position, group) in alive {
+        for index in group {
+            outcomes[index] = Some(Outcome {
+                start: starts[index].clone(),
+                end: position.clone(),
+                status: RobotStatus::Alive,
+                commands_run: commands.len(),
+            });
+        }
+    }
+
+    outcomes.into_iter().flatten().collect()
+}
+
+/// The outcomes that leave the robot alive at the goal.
+pub fn arriving_at<'a>(
+    outcomes: &'a [Outcome],
+    goal: &'a Goal,
+) -> impl Iterator<Item = &'a Outcome> {
+    outcomes
+        .iter()
+        .filter(move |outcome| outcome.status == RobotStatus::Alive && goal.reached(&outcome.end))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mission_instructions::RobotCommands;
+    use crate::parser::parse_input_to_command;
+    use crate::planner::Goal;
+    use crate::robots::{Robot, RobotPosition, RobotStatus};
+    use crate::solver::{arriving_at, run_from_every_start, Edge, Outcome};
+    use rstest::rstest;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn outcomes(mission: &str, commands: &str, scents: &[&str]) -> Vec<Outcome> {
+        let command = parse_input_to_command(mission).unwrap();
+        let commands: Vec<RobotCommands> = commands
+            .chars()
+            .map(|c| RobotCommands::from_str(&c.to_string()).unwrap())
+            .collect();
+        let scents: HashSet<RobotPosition> = scents
+            .iter()
+            .map(|scent| RobotPosition::from_str(scent).unwrap())
+            .collect();
+        run_from_every_start(&command.plateau(), &command.compass, &commands, &scents)
+    }
+
+    fn starts_arriving(mission: &str, commands: &str, goal: &str) -> Vec<String> {
+        let outcomes = outcomes(mission, commands, &[]);
+        let goal = Goal::from_str(goal).unwrap();
+        arriving_at(&outcomes, &goal)
+            .map(|outcome| outcome.start.to_string())
+            .collect()
+    }
+
+    #[rstest]
+    #[case("5 3\n", "FF", "2 0", vec!["2 2 S", "0 0 E", "4 0 W"])]
+    #[case("5 3\n", "FF", "2 0 E", vec!["0 0 E"])]
+    #[case("5 3\n", "RF", "0 0", vec!["0 1 E", "1 0 S"])]
+    #[case("5 3\n", "FFFF", "0 0 W", vec!["4 0 W"])]
+    #[case("obstacle 1 0\n5 3\n", "F", "0 0 E", vec!["0 0 E"])]
+    #[case("boundary wrap\n5 3\n", "F", "0 0 N", vec!["0 3 N"])]
+    fn test_starts_arriving(
+        #[case] mission: &str,
+        #[case] commands: &str,
+        #[case] goal: &str,
+        #[case] mut expected: Vec<&str>,
+    ) {
+        let mut starts = starts_arriving(mission, commands, goal);
+        starts.sort();
+        expected.sort();
+        assert_eq!(starts, expected);
+    }
+
+    #[test]
+    fn test_matches_running_each_robot() {
+        let command = parse_input_to_command("obstacle 2 2\n5 3\n").unwrap();
+        let commands = "FRRFLLFFRRFLL";
+
+        for outcome in outcomes("obstacle 2 2\n5 3\n", commands, &[]) {
+            let mut scents: HashSet<RobotPosition> = HashSet::new();
+            let robot = Robot::new()
+                .set_start_position(outcome.start.clone())
+                .update_commands(
+                    commands
+                        .chars()
+                        .map(|c| RobotCommands::from_str(&c.to_string()).unwrap())
+                        .collect(),
+                )
+                .process_all_commands(&command.plateau(), &mut scents);
+            assert_eq!(robot.position, outcome.end);
+            assert_eq!(robot.robot_status, outcome.status);
+        }
+    }
+
+    #[rstest]
+    #[case("5 3\n", "3 3 N", "F", Some(Edge::North))]
+    #[case("5 3\n", "0 1 W", "F", Some(Edge::West))]
+    #[case("compass 8\n5 3\n", "5 0 SE", "F", Some(Edge::SouthEast))]
+    #[case("compass 8\n5 3\n", "5 1 SE", "F", Some(Edge::East))]
+    #[case(
+        "mask ###\nmask #.#\nmask ###\n2 2\n",
+        "1 0 N",
+        "F",
+        Some(Edge::Outline)
+    )]
+    #[case("5 3\n", "1 1 N", "F", None)]
+    fn test_edges(
+        #[case] mission: &str,
+        #[case] start: &str,
+        #[case] commands: &str,
+        #[case] expected: Option<Edge>,
+    ) {
+        let command = parse_input_to_command(mission).unwrap();
+        let start = RobotPosition::from_str(start).unwrap();
+        let outcome = outcomes(mission, commands, &[])
+            .into_iter()
+            .find(|outcome| outcome.start == start)
+            .unwrap();
+
+        assert_eq!(outcome.edge(&command.plateau()), expected);
+    }
+
+    #[test]
+    fn test_scents_protect_starts() {
+        let start = RobotPosition::from_str("3 3 N").unwrap();
+        let find = |scents: &[&str]| {
+            outcomes("5 3\n", "FRF", scents)
+                .into_iter()
+                .find(|outcome| outcome.start == start)
+                .unwrap()
+        };
+
+        let lost = find(&[]);
+        assert_eq!(lost.status, RobotStatus::Lost);
+        assert_eq!(lost.commands_run, 1);
+
+        let saved = find(&["3 3 N"]);
+        assert_eq!(saved.status, RobotStatus::Alive);
+        assert_eq!(saved.end, RobotPosition::from_str("4 3 E").unwrap());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::json::Json;
+use crate::mission_instructions::Coordinates;
+use crate::report::MissionReport;
+use crate::robots::RobotStatus;
+use crate::solver::Edge;
+use std::collections::{BTreeMap, HashSet};
+
+/// Aggregate numbers for a mission, worked out from its report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissionStats {
+    pub deployed: usize,
+    pub survived: usize,
+    pub lost: usize,
+    pub crashed: usize,
+    pub collided: usize,
+    pub depleted: usize,
+    /// How many robots went over each edge, for the edges anyone went over.
+    pub losses_by_edge: BTreeMap<Edge, usize>,
+    pub squares_visited: usize,
+    pub open_squares: usize,
+    pub scents_created: usize,
+    /// Forward moves ignored because a scent would have saved the robot.
+    pub commands_ignored: usize,
+    /// Squares moved per robot.
+    pub average_path_length: f64,
+}
+
+/// Keeps the JSON and text readable.
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl MissionStats {
+    pub fn new(report: &MissionReport) -> Self {
+        let plateau = report.command.plateau();
+        let bounds = &plateau.bounds;
+        let count = |status: RobotStatus| {
+            report
+                .robots
+                .iter()
+                .filter(|robot| robot.robot_status == status)
+                .count()
+        };
+
+        let mut losses_by_edge = BTreeMap::new();
+        for robot in &report.robots {
+            if robot.robot_status == RobotStatus::Lost {
+                *losses_by_edge
+                    .entry(Edge::crossed(&robot.position, &plateau))
+                    .or_default() += 1;
+            }
+        }
+
+        let visited: HashSet<&Coordinates> = report.paths.iter().flatten().collect();
+        let mut open_squares = 0;
+        for y in bounds.lower_left.y..=bounds.upper_right.y {
+            for x in bounds.lower_left.x..=bounds.upper_right.x {
+                let square = Coordinates { x, y };
+                if !plateau.off_plateau(&square) && !plateau.is_blocked(&square) {
+                    open_squares += 1;
+                }
+            }
+        }
+
+        let moves: usize = report.paths.iter().map(|path| path.len() - 1).sum();
+        MissionStats {
+            deployed: report.robots.len(),
+            survived: count(RobotStatus::Alive),
+            lost: count(RobotStatus::Lost),
+            crashed: count(RobotStatus::Crashed),
+            collided: count(RobotStatus::Collided),
+            depleted: count(RobotStatus::Depleted),
+            losses_by_edge,
+            squares_visited: visited.len(),
+            open_squares,
+            scents_created: report.scents_created,
+            commands_ignored: report.saved_by.len(),
+            average_path_length: if report.paths.is_empty() {
+                0.0
+            } else {
+                moves as f64 / report.paths.len() as f64
+            },
+        }
+    }
+
+    /// The share of deployed robots lost over `edge`.
+    pub fn loss_rate(&self, edge: &Edge) -> f64 {
+        match (self.losses_by_edge.get(edge), self.deployed) {
+            (Some(lost), deployed) if deployed > 0 => *lost as f64 / deployed as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// The share of open squares some robot stood on, as a percentage.
+    pub fn coverage(&self) -> f64 {
+        if self.open_squares == 0 {
+            0.0
+        } else {
+            self.squares_visited as f64 * 100.0 / self.open_squares as f64
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut output = format!(
+            "robots: {} deployed, {} survived, {} lost, {} crashed, {} collided, {} depleted\n",
+            self.deployed, self.survived, self.lost, self.crashed, self.collided, self.depleted
+        );
+        for (edge, lost) in &self.losses_by_edge {
+            output.push_str(&format!(
+                "lost over the {}: {} ({:.1}% of robots)\n",
+                edge,
+                lost,
+                self.loss_rate(edge) * 100.0
+            ));
+        }
+        output.push_str(&format!(
+            "squares visited: {} of {} ({:.1}%)\n",
+            self.squares_visited,
+            self.open_squares,
+            self.coverage()
+        ));
+        output.push_str(&format!("scents created: {}\n", self.scents_created));
+        output.push_str(&format!(
+            "commands ignored because of scents: {}\n",
+            self.commands_ignored
+        ));
+        output.push_str(&format!(
+            "average path length: {:.2} squares\n",
+            self.average_path_length
+        ));
+        output
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            (
+                "robots",
+                Json::object(vec![
+                    ("deployed", Json::from(self.deployed)),
+                    ("survived", Json::from(self.survived)),
+                    ("lost", Json::from(self.lost)),
+                    ("crashed", Json::from(self.crashed)),
+                    ("collided", Json::from(self.collided)),
+                    ("depleted", Json::from(self.depleted)),
+                ]),
+            ),
+            (
+                "losses_by_edge",
+                Json::object(
+                    self.losses_by_edge
+                        .iter()
+                        .map(|(edge, lost)| {
+                            (
+                                edge.key(),
+                                Json::object(vec![
+                                    ("lost", Json::from(*lost)),
+                                    ("rate", Json::Number(round(self.loss_rate(edge)))),
+                                ]),
+                            )
+                        })
+                        .collect(),
+                ),
+            ),
+            ("squares_visited", Json::from(self.squares_visited)),
+            ("open_squares", Json::from(self.open_squares)),
+            ("coverage_percent", Json::Number(round(self.coverage()))),
+            ("scents_created", Json::from(self.scents_created)),
+            ("commands_ignored", Json::from(self.commands_ignored)),
+            (
+                "average_path_length",
+                Json::Number(round(self.average_path_length)),
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parse_input_to_command;
+    use crate::report::MissionReport;
+    use crate::scents::ScentLog;
+    use crate::simulation::{run_mission, Schedule};
+    use crate::solver::Edge;
+    use crate::stats::MissionStats;
+    use std::collections::HashSet;
+
+    fn stats(mission: &str) -> MissionStats {
+        let command = parse_input_to_command(mission).unwrap();
+        let mut scent_log = ScentLog::new(HashSet::new(), command.scent_decay);
+        let outcome = run_mission(&command, Schedule::Sequential, &mut scent_log);
+        MissionStats::new(&MissionReport::new(&command, outcome, &scent_log))
+    }
+
+    #[test]
+    fn test_sample_mission_stats() {
+        let stats = stats("5 3\n1 1 E\nRFRFRFRF\n\n3 2 N\nFRRFLLFFRRFLL\n\n0 3 W\nLLFFFLFLFL");
+
+        assert_eq!(stats.deployed, 3);
+        assert_eq!(stats.survived, 2);
+        assert_eq!(stats.lost, 1);
+        assert_eq!(stats.losses_by_edge.get(&Edge::North), Some(&1));
+        assert_eq!(stats.scents_created, 1);
+        assert_eq!(stats.commands_ignored, 1);
+        assert_eq!(stats.open_squares, 24);
+        insta::assert_display_snapshot!(stats.text())
+    }
+
+    #[test]
+    fn test_stats_json() {
+        let stats =
+            stats("obstacle 2 2\non-obstacle crash\n5 3\n1 1 E\nFF\n\n2 1 N\nF\n\n0 0 W\nF");
+
+        insta::assert_display_snapshot!(stats.to_json())
+    }
+
+    #[test]
+    fn test_stats_without_robots() {
+        let stats = stats("5 3\n");
+
+        assert_eq!(stats.deployed, 0);
+        assert_eq!(stats.average_path_length, 0.0);
+        assert_eq!(stats.coverage(), 0.0);
+    }
+}